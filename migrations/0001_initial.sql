@@ -0,0 +1,32 @@
+-- Baseline schema, matching databases created from the old `sql/init` dump
+
+CREATE TABLE IF NOT EXISTS counter (
+	emote text NOT NULL,
+	count integer DEFAULT 0 NOT NULL,
+	user_id bigint NOT NULL,
+	server_id bigint NOT NULL,
+	CONSTRAINT counter_count_check CHECK (count > 0),
+	CONSTRAINT counter_pkey PRIMARY KEY (user_id, server_id, emote)
+);
+
+CREATE TABLE IF NOT EXISTS options (
+	opt_out boolean DEFAULT false NOT NULL,
+	silent integer,
+	user_id bigint NOT NULL,
+	CONSTRAINT options_silent_check CHECK (silent >= 0),
+	CONSTRAINT options_pkey PRIMARY KEY (user_id)
+);
+
+CREATE TABLE IF NOT EXISTS server_options (
+	mute_all integer,
+	server_id bigint NOT NULL,
+	CONSTRAINT server_options_mute_all_check CHECK (mute_all >= 0),
+	CONSTRAINT server_options_pkey PRIMARY KEY (server_id)
+);
+
+CREATE TABLE IF NOT EXISTS server_patterns (
+	server_id bigint NOT NULL,
+	emote text NOT NULL,
+	pattern text NOT NULL,
+	CONSTRAINT server_patterns_pkey PRIMARY KEY (server_id, emote)
+);
@@ -0,0 +1,14 @@
+CREATE TABLE count_events (
+	id bigserial NOT NULL,
+	user_id bigint NOT NULL,
+	server_id bigint NOT NULL,
+	channel_id bigint NOT NULL,
+	message_id bigint NOT NULL,
+	emote text NOT NULL,
+	matched text NOT NULL,
+	created_at timestamp with time zone DEFAULT now() NOT NULL,
+	CONSTRAINT count_events_pkey PRIMARY KEY (id)
+);
+
+CREATE INDEX count_events_server_id_created_at_idx ON count_events (server_id, created_at);
+CREATE INDEX count_events_message_id_idx ON count_events (message_id);
@@ -0,0 +1 @@
+ALTER TABLE count_events ADD COLUMN reply_id bigint;
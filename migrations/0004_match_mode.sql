@@ -0,0 +1,4 @@
+ALTER TABLE server_options
+	ADD COLUMN match_mode text DEFAULT 'end_only' NOT NULL,
+	ADD CONSTRAINT server_options_match_mode_check
+		CHECK (match_mode IN ('end_only', 'anywhere', 'all_occurrences'));
@@ -0,0 +1,3 @@
+ALTER TABLE server_patterns
+	ADD COLUMN kind text DEFAULT 'regex' NOT NULL,
+	ADD CONSTRAINT server_patterns_kind_check CHECK (kind IN ('regex', 'custom', 'unicode'));
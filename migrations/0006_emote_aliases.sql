@@ -0,0 +1,11 @@
+CREATE TABLE emote_aliases (
+	server_id bigint NOT NULL,
+	alias text NOT NULL,
+	emote text NOT NULL,
+	CONSTRAINT emote_aliases_pkey PRIMARY KEY (server_id, alias)
+);
+
+-- the emote as it was matched, before aliases were applied
+ALTER TABLE count_events ADD COLUMN variant text;
+UPDATE count_events SET variant = emote;
+ALTER TABLE count_events ALTER COLUMN variant SET NOT NULL;
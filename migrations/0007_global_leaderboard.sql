@@ -0,0 +1,4 @@
+-- users have to consent to appearing on the global leaderboard
+ALTER TABLE options
+	ADD COLUMN global boolean DEFAULT false NOT NULL,
+	ADD COLUMN username text;
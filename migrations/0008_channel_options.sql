@@ -0,0 +1,7 @@
+CREATE TABLE channel_options (
+	server_id bigint NOT NULL,
+	channel_id bigint NOT NULL,
+	mode text NOT NULL,
+	CONSTRAINT channel_options_mode_check CHECK (mode IN ('allow', 'block')),
+	CONSTRAINT channel_options_pkey PRIMARY KEY (server_id, channel_id)
+);
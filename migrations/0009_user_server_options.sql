@@ -0,0 +1,11 @@
+-- per-server overrides of a user's options, NULL falls back to the `options` row
+CREATE TABLE user_server_options (
+	user_id bigint NOT NULL,
+	server_id bigint NOT NULL,
+	opt_out boolean,
+	silent integer,
+	-- whether `silent` overrides the global setting, a NULL `silent` then means verbose
+	silent_set boolean DEFAULT false NOT NULL,
+	CONSTRAINT user_server_options_silent_check CHECK (silent >= 0),
+	CONSTRAINT user_server_options_pkey PRIMARY KEY (user_id, server_id)
+);
@@ -0,0 +1,7 @@
+-- custom reply text per server, emote '*' is used for emotes without their own template
+CREATE TABLE reply_templates (
+	server_id bigint NOT NULL,
+	emote text NOT NULL,
+	template text NOT NULL,
+	CONSTRAINT reply_templates_pkey PRIMARY KEY (server_id, emote)
+);
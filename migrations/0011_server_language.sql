@@ -0,0 +1,3 @@
+-- the language replies to counted messages are sent in
+ALTER TABLE server_options
+	ADD COLUMN language text DEFAULT 'en' NOT NULL;
@@ -0,0 +1,11 @@
+-- whether the user opted out in the server, falling back to their global setting.
+-- a NULL server only checks the global setting
+CREATE FUNCTION opted_out(user_id bigint, server_id bigint) RETURNS boolean
+LANGUAGE sql STABLE AS $$
+	SELECT COALESCE(
+		(SELECT opt_out FROM user_server_options
+			WHERE user_server_options.user_id = $1 AND user_server_options.server_id = $2),
+		(SELECT opt_out FROM options WHERE options.user_id = $1),
+		false
+	)
+$$;
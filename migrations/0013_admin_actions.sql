@@ -0,0 +1,15 @@
+-- counts changed by server admins through /admin, with who did it and why
+CREATE TABLE admin_actions (
+	id bigserial NOT NULL,
+	server_id bigint NOT NULL,
+	actor_id bigint NOT NULL,
+	action text NOT NULL,
+	user_id bigint,
+	emote text,
+	count integer,
+	reason text NOT NULL,
+	created_at timestamp with time zone DEFAULT now() NOT NULL,
+	CONSTRAINT admin_actions_pkey PRIMARY KEY (id)
+);
+
+CREATE INDEX admin_actions_server_id_created_at_idx ON admin_actions (server_id, created_at);
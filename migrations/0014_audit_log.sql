@@ -0,0 +1,26 @@
+-- every change made through a command, replacing admin_actions
+CREATE TABLE audit_log (
+	id bigserial NOT NULL,
+	-- NULL for changes to a user's global settings
+	server_id bigint,
+	actor_id bigint NOT NULL,
+	action text NOT NULL,
+	-- what was changed, as shown in /audit
+	target text,
+	old_value text,
+	new_value text,
+	reason text,
+	created_at timestamp with time zone DEFAULT now() NOT NULL,
+	CONSTRAINT audit_log_pkey PRIMARY KEY (id)
+);
+
+CREATE INDEX audit_log_server_id_created_at_idx ON audit_log (server_id, created_at);
+
+INSERT INTO audit_log (server_id, actor_id, action, target, new_value, reason, created_at)
+SELECT server_id, actor_id, action,
+	CASE WHEN emote IS NULL THEN '<@' || user_id || '>' ELSE '<@' || user_id || '> *' || emote || '*' END,
+	count::text, reason, created_at
+FROM admin_actions
+ORDER BY id;
+
+DROP TABLE admin_actions;
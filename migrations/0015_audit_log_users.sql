@@ -0,0 +1,15 @@
+-- /audit only lists a server's changes, so changes to global settings aren't recorded anymore
+DELETE FROM audit_log WHERE server_id IS NULL;
+ALTER TABLE audit_log ALTER COLUMN server_id SET NOT NULL;
+
+-- NULL once the data of the user who made the change was deleted
+ALTER TABLE audit_log ALTER COLUMN actor_id DROP NOT NULL;
+
+-- the user whose counts or settings were changed, NULL once their data was deleted
+ALTER TABLE audit_log ADD COLUMN user_id bigint;
+
+UPDATE audit_log SET user_id = actor_id WHERE action IN ('opt_out', 'silent');
+UPDATE audit_log
+SET user_id = substring(target FROM '^<@(\d+)>')::bigint,
+	target = NULLIF(regexp_replace(target, '^<@\d+> ?', ''), '')
+WHERE action IN ('set_count', 'reset_user') AND target ~ '^<@\d+>';
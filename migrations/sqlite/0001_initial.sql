@@ -0,0 +1,114 @@
+-- The same schema as the Postgres migrations up to 0014_audit_log.
+-- Booleans are 0 or 1 and timestamps are unix seconds
+
+CREATE TABLE counter (
+	emote text NOT NULL,
+	count integer DEFAULT 0 NOT NULL,
+	user_id integer NOT NULL,
+	server_id integer NOT NULL,
+	CONSTRAINT counter_count_check CHECK (count > 0),
+	CONSTRAINT counter_pkey PRIMARY KEY (user_id, server_id, emote)
+);
+
+CREATE TABLE options (
+	opt_out boolean DEFAULT false NOT NULL,
+	silent integer,
+	user_id integer NOT NULL,
+	-- users have to consent to appearing on the global leaderboard
+	global boolean DEFAULT false NOT NULL,
+	username text,
+	CONSTRAINT options_silent_check CHECK (silent >= 0),
+	CONSTRAINT options_pkey PRIMARY KEY (user_id)
+);
+
+CREATE TABLE server_options (
+	mute_all integer,
+	server_id integer NOT NULL,
+	match_mode text DEFAULT 'end_only' NOT NULL,
+	-- the language replies to counted messages are sent in
+	language text DEFAULT 'en' NOT NULL,
+	CONSTRAINT server_options_mute_all_check CHECK (mute_all >= 0),
+	CONSTRAINT server_options_match_mode_check
+		CHECK (match_mode IN ('end_only', 'anywhere', 'all_occurrences')),
+	CONSTRAINT server_options_pkey PRIMARY KEY (server_id)
+);
+
+CREATE TABLE server_patterns (
+	server_id integer NOT NULL,
+	emote text NOT NULL,
+	pattern text NOT NULL,
+	kind text DEFAULT 'regex' NOT NULL,
+	CONSTRAINT server_patterns_kind_check CHECK (kind IN ('regex', 'custom', 'unicode')),
+	CONSTRAINT server_patterns_pkey PRIMARY KEY (server_id, emote)
+);
+
+CREATE TABLE count_events (
+	id integer NOT NULL,
+	user_id integer NOT NULL,
+	server_id integer NOT NULL,
+	channel_id integer NOT NULL,
+	message_id integer NOT NULL,
+	emote text NOT NULL,
+	-- the emote as it was matched, before aliases were applied
+	variant text NOT NULL,
+	matched text NOT NULL,
+	reply_id integer,
+	created_at integer DEFAULT (unixepoch()) NOT NULL,
+	CONSTRAINT count_events_pkey PRIMARY KEY (id)
+);
+
+CREATE INDEX count_events_server_id_created_at_idx ON count_events (server_id, created_at);
+CREATE INDEX count_events_message_id_idx ON count_events (message_id);
+
+CREATE TABLE emote_aliases (
+	server_id integer NOT NULL,
+	alias text NOT NULL,
+	emote text NOT NULL,
+	CONSTRAINT emote_aliases_pkey PRIMARY KEY (server_id, alias)
+);
+
+CREATE TABLE channel_options (
+	server_id integer NOT NULL,
+	channel_id integer NOT NULL,
+	mode text NOT NULL,
+	CONSTRAINT channel_options_mode_check CHECK (mode IN ('allow', 'block')),
+	CONSTRAINT channel_options_pkey PRIMARY KEY (server_id, channel_id)
+);
+
+-- per-server overrides of a user's options, NULL falls back to the `options` row
+CREATE TABLE user_server_options (
+	user_id integer NOT NULL,
+	server_id integer NOT NULL,
+	opt_out boolean,
+	silent integer,
+	-- whether `silent` overrides the global setting, a NULL `silent` then means verbose
+	silent_set boolean DEFAULT false NOT NULL,
+	CONSTRAINT user_server_options_silent_check CHECK (silent >= 0),
+	CONSTRAINT user_server_options_pkey PRIMARY KEY (user_id, server_id)
+);
+
+-- custom reply text per server, emote '*' is used for emotes without their own template
+CREATE TABLE reply_templates (
+	server_id integer NOT NULL,
+	emote text NOT NULL,
+	template text NOT NULL,
+	CONSTRAINT reply_templates_pkey PRIMARY KEY (server_id, emote)
+);
+
+-- every change made through a command
+CREATE TABLE audit_log (
+	id integer NOT NULL,
+	-- NULL for changes to a user's global settings
+	server_id integer,
+	actor_id integer NOT NULL,
+	action text NOT NULL,
+	-- what was changed, as shown in /audit
+	target text,
+	old_value text,
+	new_value text,
+	reason text,
+	created_at integer DEFAULT (unixepoch()) NOT NULL,
+	CONSTRAINT audit_log_pkey PRIMARY KEY (id)
+);
+
+CREATE INDEX audit_log_server_id_created_at_idx ON audit_log (server_id, created_at);
@@ -0,0 +1,37 @@
+-- The same as the Postgres migration 0015_audit_log_users.
+-- SQLite can't change a column's constraints, so the table is copied into a new one
+
+CREATE TABLE audit_log_new (
+	id integer NOT NULL,
+	server_id integer NOT NULL,
+	-- NULL once the data of the user who made the change was deleted
+	actor_id integer,
+	-- the user whose counts or settings were changed, NULL once their data was deleted
+	user_id integer,
+	action text NOT NULL,
+	-- what was changed, as shown in /audit
+	target text,
+	old_value text,
+	new_value text,
+	reason text,
+	created_at integer DEFAULT (unixepoch()) NOT NULL,
+	CONSTRAINT audit_log_pkey PRIMARY KEY (id)
+);
+
+INSERT INTO audit_log_new (id, server_id, actor_id, user_id, action, target, old_value, new_value, reason, created_at)
+SELECT id, server_id, actor_id,
+	CASE WHEN action IN ('opt_out', 'silent') THEN actor_id END,
+	action, target, old_value, new_value, reason, created_at
+FROM audit_log
+WHERE server_id IS NOT NULL
+ORDER BY id;
+
+DROP TABLE audit_log;
+ALTER TABLE audit_log_new RENAME TO audit_log;
+
+UPDATE audit_log
+SET user_id = CAST(substr(target, 3, instr(target, '>') - 3) AS integer),
+	target = NULLIF(ltrim(substr(target, instr(target, '>') + 1)), '')
+WHERE action IN ('set_count', 'reset_user') AND target LIKE '<@%>%';
+
+CREATE INDEX audit_log_server_id_created_at_idx ON audit_log (server_id, created_at);
@@ -1 +0,0 @@
-pg_dump x3_counter --schema-only --no-owner --no-privileges > init/01_schema.sql
@@ -1,35 +1,49 @@
-use std::{
-	collections::{hash_map::Entry, HashMap},
-	str,
-};
+use std::{env, str};
 
 use serenity::all::{
-	CommandDataOptionValue, CommandInteraction, CreateAllowedMentions,
+	ButtonStyle, CommandDataOption, CommandDataOptionValue, CommandInteraction,
+	ComponentInteraction, CreateActionRow, CreateAllowedMentions, CreateAttachment,
+	CreateAutocompleteResponse, CreateButton, CreateEmbed, CreateEmbedFooter,
 	CreateInteractionResponseMessage, Permissions,
 };
 
 use crate::{
 	arg,
-	command::command,
-	database::{DatabaseHandler, LeaderboardRow},
+	command::{command, IntoCommand},
+	database::{
+		Change, ChannelMode, CounterStore, DatabaseHandler, EmoteOrder, LeaderboardPage,
+		LeaderboardRow, Period,
+	},
+	locale::Language,
+	matcher::{emote_label, MatchMode, Matcher, PatternKind, ServerPattern, MAX_PATTERN_LENGTH},
+	template::{render, ReplyTemplate, TemplateValues, ANY_EMOTE},
+	tr,
 };
 
-use super::args::{BaseArg, IntArg, IntoCommandArg, StringArg, UserArg};
+use super::args::{BaseArg, ChannelArg, IntArg, IntoCommandArg, StringArg, SubCommandArg, UserArg};
 
-fn postfix(count: i64) -> &'static str {
-	match count % 10 {
-		2 => "nd",
-		3 => "rd",
-		_ => "th",
-	}
-}
+const MAX_SERVER_PATTERNS: usize = 20;
+const MAX_EMOTE_LENGTH: usize = 64;
+const MAX_SERVER_ALIASES: usize = 50;
 
 macro_rules! response {
-	(server error) => {
-		return response!("You can only run this command in a server (this should not be possible)")
+	(server error, $lang:expr) => {
+		return response!(tr!(
+			$lang,
+			"You can only run this command in a server (this should not be possible)"
+		))
+	};
+	(argument error, $lang:expr) => {
+		return response!(tr!(
+			$lang,
+			"Incorrect argument type provided (this should not be possible)"
+		))
 	};
-	(argument error) => {
-		return response!("Incorrect argument type provided (this should not be possible)")
+	(scope error, $lang:expr) => {
+		return response!(tr!(
+			$lang,
+			"You can only change a setting for this server in a server"
+		))
 	};
 	($res:expr) => {
 		Ok(CreateInteractionResponseMessage::new()
@@ -54,76 +68,514 @@ const REPEAT_ARG: IntArg = arg!(
 	Some(i32::MAX as u64)
 );
 
+const SCOPE_ARG: StringArg = arg!(
+	String,
+	"scope",
+	"Change the setting only in this server, or everywhere (the default)",
+	false,
+	"server\nglobal"
+);
+
 const LEADERBOARD_COUNT_ARG: IntArg = arg!(
 	Int,
 	"count",
-	"Max count of users per emote",
+	"Users per emote on each page",
 	false,
 	Some(1),
-	Some(5)
+	Some(20)
 );
+/// Short enough for the custom ids of the buttons to fit an emote of `MAX_EMOTE_LENGTH`
+/// in the 100 characters Discord allows
+const LEADERBOARD_PAGE_ID: &str = "lb";
+const GLOBAL_LEADERBOARD_PAGE_ID: &str = "glb";
+const DEFAULT_PAGE_SIZE: i64 = 10;
+/// Rows on a page of every emote's leaderboard, few enough for the embed to stay
+/// within the 6000 characters Discord allows
+const MAX_ALL_EMOTES_ROWS: u32 = 40;
+const MAX_EMBED_FIELDS: usize = 25;
+const DELETE_DATA_ID: &str = "delete_data";
+const RESET_SERVER_ID: &str = "reset_server";
+/// Short enough for the reason to fit in the custom id of the confirmation button
+const MAX_REASON_LENGTH: usize = 60;
+/// Few enough for the list to fit in an embed with long values
+const AUDIT_LOG_ENTRIES: u32 = 10;
+const MAX_AUDIT_VALUE_LENGTH: usize = 80;
 
 const LEADERBOARD_EMOTE_ARG: StringArg = arg!(
 	String,
 	"emote",
-	"Which emote leaderboard to display",
-	true,
-	include_str!("emotes.txt")
+	"Which emote leaderboard to display, all emotes combined if not set",
+	false,
+	autocomplete
+);
+
+const LEADERBOARD_ORDER_ARG: StringArg = arg!(
+	String,
+	"order",
+	"How to order the emotes when displaying every emote",
+	false,
+	"alphabetical\nmost counted"
+);
+
+const LEADERBOARD_PERIOD_ARG: StringArg = arg!(
+	String,
+	"period",
+	"Which period to count x3s in",
+	false,
+	"today\nthis week\nthis month\nall time"
 );
 
 const COUNTS_USER_ARG: UserArg = arg!(User, "user", "The user whose counts to display", false);
 
-command!(OptInCommand, "opt_in", "Start tracking x3s");
+const PATTERN_EMOTE_ARG: StringArg = arg!(
+	String,
+	"emote",
+	"The emote name to count matches as",
+	true,
+	""
+);
+const PATTERN_REGEX_ARG: StringArg = arg!(
+	String,
+	"pattern",
+	"Regex the message has to end with",
+	true,
+	""
+);
+const PATTERN_ADD_ARG: SubCommandArg = arg!(
+	SubCommand,
+	"add",
+	"Count a new emote in this server",
+	[PATTERN_EMOTE_ARG, PATTERN_REGEX_ARG]
+);
+const PATTERN_EMOJI_VALUE_ARG: StringArg = arg!(
+	String,
+	"emoji",
+	"The custom or unicode emoji to count",
+	true,
+	""
+);
+const PATTERN_EMOJI_ARG: SubCommandArg = arg!(
+	SubCommand,
+	"emoji",
+	"Count an emoji in this server",
+	[PATTERN_EMOJI_VALUE_ARG]
+);
+const PATTERN_REMOVE_ARG: SubCommandArg = arg!(
+	SubCommand,
+	"remove",
+	"Stop counting an emote in this server",
+	[PATTERN_EMOTE_ARG]
+);
+const PATTERN_LIST_ARG: SubCommandArg = arg!(
+	SubCommand,
+	"list",
+	"List the emotes counted in this server",
+	[]
+);
+
+const ALIAS_VARIANT_ARG: StringArg = arg!(
+	String,
+	"variant",
+	"The emote to count as another one",
+	true,
+	""
+);
+const ALIAS_EMOTE_ARG: StringArg = arg!(
+	String,
+	"emote",
+	"The emote to count the variant as",
+	true,
+	""
+);
+const ALIAS_ADD_ARG: SubCommandArg = arg!(
+	SubCommand,
+	"add",
+	"Count an emote as another one in this server",
+	[ALIAS_VARIANT_ARG, ALIAS_EMOTE_ARG]
+);
+const ALIAS_REMOVE_ARG: SubCommandArg = arg!(
+	SubCommand,
+	"remove",
+	"Count a variant as its own emote again",
+	[ALIAS_VARIANT_ARG]
+);
+const ALIAS_LIST_ARG: SubCommandArg = arg!(
+	SubCommand,
+	"list",
+	"List the emote aliases in this server",
+	[]
+);
+
+const CHANNEL_ARG: ChannelArg = arg!(Channel, "channel", "The channel to configure", true);
+const CHANNEL_BLOCK_ARG: SubCommandArg = arg!(
+	SubCommand,
+	"block",
+	"Never count x3s in a channel",
+	[CHANNEL_ARG]
+);
+const CHANNEL_ALLOW_ARG: SubCommandArg = arg!(
+	SubCommand,
+	"allow",
+	"Only count x3s in allowed channels, starting with this one",
+	[CHANNEL_ARG]
+);
+const CHANNEL_RESET_ARG: SubCommandArg = arg!(
+	SubCommand,
+	"reset",
+	"Remove a channel from the allowed or blocked channels",
+	[CHANNEL_ARG]
+);
+const CHANNEL_LIST_ARG: SubCommandArg = arg!(
+	SubCommand,
+	"list",
+	"List the allowed and blocked channels",
+	[]
+);
+
+const TEMPLATE_ARG: StringArg = arg!(
+	String,
+	"template",
+	"The reply, can use {user}, {emote}, {count}, {ordinal} and {rank}",
+	true,
+	""
+);
+const TEMPLATE_EMOTE_ARG: StringArg = arg!(
+	String,
+	"emote",
+	"Only use the template for this emote",
+	false,
+	""
+);
+const TEMPLATE_SET_ARG: SubCommandArg = arg!(
+	SubCommand,
+	"set",
+	"Change the reply to counted x3s in this server",
+	[TEMPLATE_ARG, TEMPLATE_EMOTE_ARG]
+);
+const TEMPLATE_RESET_ARG: SubCommandArg = arg!(
+	SubCommand,
+	"reset",
+	"Go back to the default reply",
+	[TEMPLATE_EMOTE_ARG]
+);
+const TEMPLATE_LIST_ARG: SubCommandArg = arg!(
+	SubCommand,
+	"list",
+	"List the reply templates in this server",
+	[]
+);
+const TEMPLATE_PREVIEW_TEMPLATE_ARG: StringArg = arg!(
+	String,
+	"template",
+	"The template to preview, the saved one if not set",
+	false,
+	""
+);
+const TEMPLATE_PREVIEW_ARG: SubCommandArg = arg!(
+	SubCommand,
+	"preview",
+	"Show what a reply would look like",
+	[TEMPLATE_PREVIEW_TEMPLATE_ARG, TEMPLATE_EMOTE_ARG]
+);
+
+const MATCH_MODE_ARG: StringArg = arg!(
+	String,
+	"mode",
+	"Which emotes in a message to count",
+	true,
+	"end_only\nanywhere\nall_occurrences"
+);
+
+const DELETE_USER_ARG: UserArg = arg!(User, "user", "The user whose data to delete", true);
+const DELETE_SCOPE_ARG: StringArg = arg!(
+	String,
+	"scope",
+	"Delete the data only in this server (the default), or everywhere (bot owner only)",
+	false,
+	"server\nglobal"
+);
+
+const LANGUAGE_ARG: StringArg = arg!(
+	String,
+	"language",
+	"The language to reply to counted x3s in",
+	true,
+	"english\ndeutsch"
+);
+
+const ADMIN_USER_ARG: UserArg = arg!(User, "user", "The user whose counts to change", true);
+const ADMIN_EMOTE_ARG: StringArg = arg!(
+	String,
+	"emote",
+	"The emote whose count to set",
+	true,
+	autocomplete
+);
+const ADMIN_COUNT_ARG: IntArg = arg!(
+	Int,
+	"count",
+	"The new count, 0 removes it",
+	true,
+	Some(0),
+	Some(i32::MAX as u64)
+);
+const ADMIN_REASON_ARG: StringArg = arg!(
+	String,
+	"reason",
+	"Why the counts are changed, kept with the change",
+	true,
+	""
+);
+const ADMIN_SET_COUNT_ARG: SubCommandArg = arg!(
+	SubCommand,
+	"set_count",
+	"Set a user's count of an emote in this server",
+	[
+		ADMIN_USER_ARG,
+		ADMIN_EMOTE_ARG,
+		ADMIN_COUNT_ARG,
+		ADMIN_REASON_ARG
+	]
+);
+const ADMIN_RESET_USER_ARG: SubCommandArg = arg!(
+	SubCommand,
+	"reset_user",
+	"Remove a user's counts in this server",
+	[ADMIN_USER_ARG, ADMIN_REASON_ARG]
+);
+const ADMIN_RESET_SERVER_ARG: SubCommandArg = arg!(
+	SubCommand,
+	"reset_server",
+	"Remove every count in this server",
+	[ADMIN_REASON_ARG]
+);
+
+fn int_arg(args: &[CommandDataOption], name: &str) -> Option<i64> {
+	args.iter()
+		.find(|arg| arg.name == name)
+		.and_then(|arg| arg.value.as_i64())
+}
+
+fn user_arg(args: &[CommandDataOption], name: &str) -> Option<u64> {
+	args.iter()
+		.find(|arg| arg.name == name)
+		.and_then(|arg| arg.value.as_user_id())
+		.map(|id| id.get())
+}
+
+fn channel_arg(args: &[CommandDataOption], name: &str) -> Option<u64> {
+	args.iter()
+		.find(|arg| arg.name == name)
+		.and_then(|arg| arg.value.as_channel_id())
+		.map(|id| id.get())
+}
+
+fn string_arg<'a>(args: &'a [CommandDataOption], name: &str) -> Option<&'a str> {
+	args.iter()
+		.find(|arg| arg.name == name)
+		.and_then(|arg| arg.value.as_str())
+}
+
+/// The server a user setting is changed in, `Some(None)` for the global setting.
+/// `None` if the server scope was chosen outside of a server
+fn setting_server(cmd: &CommandInteraction) -> Option<Option<u64>> {
+	match string_arg(&cmd.data.options, SCOPE_ARG.base.name) {
+		Some("server") => cmd.guild_id.map(|id| Some(id.get())),
+		_ => Some(None),
+	}
+}
+
+/// The reply to commands that need the database when the bot runs without one
+pub fn database_required(cmd: &CommandInteraction) -> CreateInteractionResponseMessage {
+	let lang = Language::from_locale(&cmd.locale);
+	CreateInteractionResponseMessage::new()
+		.content(tr!(
+			lang,
+			"This command needs the database, which I'm running without right now"
+		))
+		.ephemeral(true)
+		.allowed_mentions(CreateAllowedMentions::new())
+}
+
+/// A change without a reason, for the audit log
+fn change(
+	action: &'static str,
+	target: Option<String>,
+	old_value: Option<String>,
+	new_value: Option<String>,
+) -> Change<'static> {
+	Change {
+		action,
+		user_id: None,
+		target,
+		old_value,
+		new_value,
+		reason: None,
+	}
+}
+
+/// Records a change to the user's own setting, only in a server since `/audit` lists a server's changes
+async fn audit_setting(
+	db: &dyn CounterStore,
+	user_id: u64,
+	server_id: Option<u64>,
+	change: Change<'_>,
+) -> sqlx::Result<()> {
+	let Some(server_id) = server_id else {
+		return Ok(());
+	};
+	let change = Change {
+		user_id: Some(user_id.into()),
+		..change
+	};
+	db.audit(user_id, server_id, change).await
+}
+
+/// How a silent setting or muting the server is shown in the audit log
+fn silent_value(value: Option<u32>) -> String {
+	match value {
+		None => "verbose".to_owned(),
+		Some(0) => "silent".to_owned(),
+		Some(nth) => format!("every {nth}"),
+	}
+}
+
+command!(OptInCommand, "opt_in", "Start tracking x3s", [SCOPE_ARG]);
 pub async fn opt_in(
-	db: &DatabaseHandler,
+	db: &dyn CounterStore,
 	cmd: &CommandInteraction,
 ) -> sqlx::Result<CreateInteractionResponseMessage> {
+	let lang = Language::from_locale(&cmd.locale);
 	let user_id = cmd.user.id.get();
+	let Some(server_id) = setting_server(cmd) else {
+		response!(scope error, lang);
+	};
 
-	db.set_opt_out(user_id, false).await?;
-	response!("I will count your ':3's now UwU")
+	let old = db.set_opt_out(user_id, server_id, false).await?;
+	let change = change(
+		"opt_out",
+		None,
+		old.map(|old| old.to_string()),
+		Some(false.to_string()),
+	);
+	audit_setting(db, user_id, server_id, change).await?;
+	response!(match server_id {
+		Some(_) => tr!(lang, "I will count your ':3's in this server now UwU"),
+		None => tr!(lang, "I will count your ':3's now UwU"),
+	})
 }
 
-command!(OptOutCommand, "opt_out", "Stop tracking x3s");
+command!(OptOutCommand, "opt_out", "Stop tracking x3s", [SCOPE_ARG]);
 pub async fn opt_out(
-	db: &DatabaseHandler,
+	db: &dyn CounterStore,
+	cmd: &CommandInteraction,
+) -> sqlx::Result<CreateInteractionResponseMessage> {
+	let lang = Language::from_locale(&cmd.locale);
+	let user_id = cmd.user.id.get();
+	let Some(server_id) = setting_server(cmd) else {
+		response!(scope error, lang);
+	};
+
+	let old = db.set_opt_out(user_id, server_id, true).await?;
+	let change = change(
+		"opt_out",
+		None,
+		old.map(|old| old.to_string()),
+		Some(true.to_string()),
+	);
+	audit_setting(db, user_id, server_id, change).await?;
+	response!(match server_id {
+		Some(_) => tr!(lang, "I won't count your ':3's in this server now qwq"),
+		None => tr!(lang, "I won't count your ':3's now qwq"),
+	})
+}
+
+command!(
+	GlobalOptInCommand,
+	"global_opt_in",
+	"Appear on the global leaderboard with your username"
+);
+pub async fn global_opt_in(
+	db: &dyn CounterStore,
+	cmd: &CommandInteraction,
+) -> sqlx::Result<CreateInteractionResponseMessage> {
+	let lang = Language::from_locale(&cmd.locale);
+	let user_id = cmd.user.id.get();
+	let username = cmd.user.global_name.as_ref().unwrap_or(&cmd.user.name);
+
+	db.set_global(user_id, Some(username)).await?;
+	response!(tr!(
+		lang,
+		"You will appear on the global leaderboard as {username} (run this again if you change your name)",
+		username = username
+	))
+}
+
+command!(
+	GlobalOptOutCommand,
+	"global_opt_out",
+	"Stop appearing on the global leaderboard"
+);
+pub async fn global_opt_out(
+	db: &dyn CounterStore,
 	cmd: &CommandInteraction,
 ) -> sqlx::Result<CreateInteractionResponseMessage> {
+	let lang = Language::from_locale(&cmd.locale);
 	let user_id = cmd.user.id.get();
 
-	db.set_opt_out(user_id, true).await?;
-	response!("I won't count your ':3's now qwq")
+	db.set_global(user_id, None).await?;
+	response!(tr!(
+		lang,
+		"You won't appear on the global leaderboard anymore"
+	))
 }
 
 command!(
 	SilentCommand,
 	"silent",
 	"Track x3s silently (don't send messages)",
-	[REPEAT_ARG]
+	[REPEAT_ARG, SCOPE_ARG]
 );
 pub async fn silent(
-	db: &DatabaseHandler,
+	db: &dyn CounterStore,
 	cmd: &CommandInteraction,
 ) -> sqlx::Result<CreateInteractionResponseMessage> {
+	let lang = Language::from_locale(&cmd.locale);
 	let user_id = cmd.user.id.get();
+	let Some(server_id) = setting_server(cmd) else {
+		response!(scope error, lang);
+	};
 
-	let content = match cmd.data.options.as_slice() {
-		[] => {
-			db.set_silent(user_id, Some(0)).await?;
-			"I won't respond to your messages but will still count x3s".to_owned()
-		}
-		[arg, ..] => {
-			if let CommandDataOptionValue::Integer(count) = arg.value {
-				db.set_silent(user_id, Some(count as u32)).await?;
+	let value = int_arg(&cmd.data.options, REPEAT_ARG.base.name).map_or(0, |count| count as u32);
+	let old = db.set_silent(user_id, server_id, Some(value)).await?;
+	let change = change(
+		"silent",
+		None,
+		old.map(silent_value),
+		Some(silent_value(Some(value))),
+	);
+	audit_setting(db, user_id, server_id, change).await?;
 
-				format!(
-					"I will only respond to every {}{} x3",
-					count,
-					postfix(count)
-				)
-			} else {
-				eprintln!("Argument {} has incorrect type", arg.name);
-				response!(argument error);
+	let content = match int_arg(&cmd.data.options, REPEAT_ARG.base.name) {
+		None => match server_id {
+			Some(_) => tr!(
+				lang,
+				"I won't respond to your messages in this server but will still count x3s"
+			),
+			None => tr!(
+				lang,
+				"I won't respond to your messages but will still count x3s"
+			),
+		},
+		Some(count) => {
+			let nth = lang.ordinal(count);
+			match server_id {
+				Some(_) => tr!(
+					lang,
+					"I will only respond to every {nth} x3 in this server",
+					nth = nth
+				),
+				None => tr!(lang, "I will only respond to every {nth} x3", nth = nth),
 			}
 		}
 	};
@@ -134,16 +586,31 @@ pub async fn silent(
 command!(
 	VerboseCommand,
 	"verbose",
-	"Track x3s verbosely (do send messages)"
+	"Track x3s verbosely (do send messages)",
+	[SCOPE_ARG]
 );
 pub async fn verbose(
-	db: &DatabaseHandler,
+	db: &dyn CounterStore,
 	cmd: &CommandInteraction,
 ) -> sqlx::Result<CreateInteractionResponseMessage> {
+	let lang = Language::from_locale(&cmd.locale);
 	let user_id = cmd.user.id.get();
+	let Some(server_id) = setting_server(cmd) else {
+		response!(scope error, lang);
+	};
 
-	db.set_silent(user_id, None).await?;
-	response!("I will now respond to your messages")
+	let old = db.set_silent(user_id, server_id, None).await?;
+	let change = change(
+		"silent",
+		None,
+		old.map(silent_value),
+		Some(silent_value(None)),
+	);
+	audit_setting(db, user_id, server_id, change).await?;
+	response!(match server_id {
+		Some(_) => tr!(lang, "I will now respond to your messages in this server"),
+		None => tr!(lang, "I will now respond to your messages"),
+	})
 }
 
 command!(
@@ -153,9 +620,10 @@ command!(
 	[COUNTS_USER_ARG]
 );
 pub async fn counts(
-	db: &DatabaseHandler,
+	db: &dyn CounterStore,
 	cmd: &CommandInteraction,
 ) -> sqlx::Result<CreateInteractionResponseMessage> {
+	let lang = Language::from_locale(&cmd.locale);
 	let user_id = cmd.user.id.get();
 
 	let user_arg = match cmd.data.options.as_slice() {
@@ -165,24 +633,34 @@ pub async fn counts(
 				Some(user_arg_id.get())
 			} else {
 				eprintln!("Argument {} has incorrect type", arg.name);
-				response!(argument error);
+				response!(argument error, lang);
 			}
 		}
 	};
 
 	let query_user_id = user_arg.unwrap_or(user_id);
 
-	let counts = match cmd.guild_id {
-		Some(server_id) => {
-			db.get_user_server_counts(query_user_id, server_id.get())
-				.await?
-		}
-		None => db.get_user_counts(user_id).await?,
+	let server_id = cmd.guild_id.map(|id| id.get());
+	if db.is_opt_out(query_user_id, server_id).await? {
+		return response!(if user_id == query_user_id {
+			tr!(lang, "You opted out, so I don't count your x3s :c")
+		} else {
+			tr!(
+				lang,
+				"<@{user}> opted out, so I don't count their x3s :c",
+				user = query_user_id
+			)
+		});
+	}
+
+	let counts = match server_id {
+		Some(server_id) => db.user_counts(query_user_id, Some(server_id)).await?,
+		None => db.user_counts(user_id, None).await?,
 	};
 
 	let content = match (counts.as_slice(), user_id == query_user_id) {
-		(&[], true) => "You don't have any x3s yet :c".to_owned(),
-		(&[], false) => "This user doesn't have any x3s yet :c".to_owned(),
+		(&[], true) => tr!(lang, "You don't have any x3s yet :c"),
+		(&[], false) => tr!(lang, "This user doesn't have any x3s yet :c"),
 		(counts, is_user) => {
 			let counts_str = counts
 				.iter()
@@ -190,9 +668,14 @@ pub async fn counts(
 				.collect::<Vec<_>>()
 				.join("\n");
 			if is_user {
-				format!("Here are your counts:\n{counts_str}")
+				tr!(lang, "Here are your counts:\n{counts}", counts = counts_str)
 			} else {
-				format!("Here are <@{query_user_id}>'s counts:\n{counts_str}")
+				tr!(
+					lang,
+					"Here are <@{user}>'s counts:\n{counts}",
+					user = query_user_id,
+					counts = counts_str
+				)
 			}
 		}
 	};
@@ -204,68 +687,297 @@ command!(
 	"leaderboard",
 	"Get the x3 leaderboard for this server",
 	false,
-	[LEADERBOARD_EMOTE_ARG, LEADERBOARD_COUNT_ARG]
+	[
+		LEADERBOARD_EMOTE_ARG,
+		LEADERBOARD_COUNT_ARG,
+		LEADERBOARD_PERIOD_ARG,
+		LEADERBOARD_ORDER_ARG
+	]
 );
 pub async fn leaderboard(
-	db: &DatabaseHandler,
+	db: &dyn CounterStore,
 	cmd: &CommandInteraction,
 ) -> sqlx::Result<CreateInteractionResponseMessage> {
+	let lang = Language::from_locale(&cmd.locale);
 	let Some(server_id) = cmd.guild_id else {
-		response!(server error);
+		response!(server error, lang);
 	};
-	let server_id = server_id.get();
 
-	let mut args = cmd.data.options.to_owned();
-	args.sort_by(|l, r| l.name.cmp(&r.name));
+	let Some(view) = LeaderboardView::from_args(&cmd.data.options, false) else {
+		response!(argument error, lang);
+	};
+	leaderboard_message(db, lang, Some(server_id.get()), &view).await
+}
 
-	let leaderboard = match args.as_slice() {
-		[] => {
-			eprintln!("No arguments provided when emote is required");
-			response!(argument error);
-		}
-		[emote_arg] => {
-			if let CommandDataOptionValue::String(emote) = &emote_arg.value {
-				db.leaderboard(server_id, 3i64, emote).await?
-			} else {
-				eprintln!("Only argument should be emote argument");
-				response!(argument error);
-			}
-		}
-		[count_arg, emote_arg, ..] => match (&count_arg.value, &emote_arg.value) {
-			(CommandDataOptionValue::Integer(count), CommandDataOptionValue::String(emote)) => {
-				db.leaderboard(server_id, *count, emote).await?
-			}
-			(CommandDataOptionValue::Integer(_), _) => {
-				eprintln!("Argument {} has incorrect type", emote_arg.name);
-				response!(argument error);
-			}
-			(_, _) => {
-				eprintln!("Argument {} has incorrect type", count_arg.name);
-				response!(argument error);
-			}
-		},
+command!(
+	GlobalLeaderboardCommand,
+	"global_leaderboard",
+	"Get the x3 leaderboard across every server",
+	[
+		LEADERBOARD_EMOTE_ARG,
+		LEADERBOARD_COUNT_ARG,
+		LEADERBOARD_PERIOD_ARG,
+		LEADERBOARD_ORDER_ARG
+	]
+);
+pub async fn global_leaderboard(
+	db: &dyn CounterStore,
+	cmd: &CommandInteraction,
+) -> sqlx::Result<CreateInteractionResponseMessage> {
+	let lang = Language::from_locale(&cmd.locale);
+	let Some(view) = LeaderboardView::from_args(&cmd.data.options, true) else {
+		response!(argument error, lang);
 	};
-	let mut emote_map: HashMap<Box<str>, Vec<LeaderboardRow>> = HashMap::new();
-	for row in leaderboard {
-		match emote_map.entry(row.emote.clone()) {
-			Entry::Occupied(mut o) => o.get_mut().push(row),
-			Entry::Vacant(v) => v.insert(Default::default()).push(row),
+	leaderboard_message(db, lang, None, &view).await
+}
+
+/// What a leaderboard message shows, encoded into the custom ids of its buttons
+struct LeaderboardView<'a> {
+	global: bool,
+	/// `None` ranks all emotes combined
+	emote: Option<&'a str>,
+	order: EmoteOrder,
+	period: Period,
+	page: u32,
+	size: u32,
+}
+
+impl<'a> LeaderboardView<'a> {
+	fn from_args(args: &'a [CommandDataOption], global: bool) -> Option<Self> {
+		let emote = string_arg(args, LEADERBOARD_EMOTE_ARG.base.name);
+		let size = int_arg(args, LEADERBOARD_COUNT_ARG.base.name).unwrap_or(DEFAULT_PAGE_SIZE);
+		let period = match string_arg(args, LEADERBOARD_PERIOD_ARG.base.name) {
+			None => Period::All,
+			Some(period) => Period::from_choice(period).or_else(|| {
+				eprintln!("Unknown period {period}");
+				None
+			})?,
+		};
+		let order = match string_arg(args, LEADERBOARD_ORDER_ARG.base.name) {
+			None => EmoteOrder::default(),
+			Some(order) => EmoteOrder::from_choice(order).or_else(|| {
+				eprintln!("Unknown emote order {order}");
+				None
+			})?,
+		};
+
+		Some(Self {
+			global,
+			emote,
+			order,
+			period,
+			page: 0,
+			size: size as u32,
+		})
+	}
+
+	fn custom_id(&self, page: u32) -> String {
+		format!(
+			"{}:{page}:{}:{}:{}:{}",
+			self.prefix(),
+			self.size,
+			self.period.as_str(),
+			self.order.as_str(),
+			self.emote.unwrap_or_default()
+		)
+	}
+
+	fn prefix(&self) -> &'static str {
+		if self.global {
+			GLOBAL_LEADERBOARD_PAGE_ID
+		} else {
+			LEADERBOARD_PAGE_ID
 		}
 	}
-	let content = emote_map
-		.into_iter()
-		.map(|(emote, rows)| {
-			let rows_str = rows
-				.iter()
-				.map(ToString::to_string)
-				.collect::<Box<[_]>>()
-				.join("\n");
-			format!("## {emote}\n{rows_str}")
+
+	fn parse(custom_id: &'a str) -> Option<Self> {
+		let mut parts = custom_id.splitn(6, ':');
+		let global = match parts.next()? {
+			LEADERBOARD_PAGE_ID => false,
+			GLOBAL_LEADERBOARD_PAGE_ID => true,
+			_ => return None,
+		};
+		Some(Self {
+			global,
+			page: parts.next()?.parse().ok()?,
+			size: parts.next()?.parse().ok()?,
+			period: Period::from_str(parts.next()?)?,
+			order: EmoteOrder::from_str(parts.next()?)?,
+			emote: Some(parts.next()?).filter(|emote| !emote.is_empty()),
 		})
-		.collect::<Box<[_]>>()
-		.join("\n");
+	}
+}
 
-	response!(content, false)
+async fn leaderboard_message(
+	db: &dyn CounterStore,
+	lang: Language,
+	server_id: Option<u64>,
+	view: &LeaderboardView<'_>,
+) -> sqlx::Result<CreateInteractionResponseMessage> {
+	// the emote is typed freely, and has to fit in the custom ids of the buttons
+	if view
+		.emote
+		.is_some_and(|emote| emote.chars().count() > MAX_EMOTE_LENGTH)
+	{
+		return response!(tr!(
+			lang,
+			"Emotes must be between 1 and {max} characters long",
+			max = MAX_EMOTE_LENGTH
+		));
+	}
+
+	// every emote's leaderboard pages through the emotes, showing the top users of each
+	let all_emotes = view.emote == Some("*");
+	let offset = if all_emotes { 0 } else { view.page * view.size };
+	let LeaderboardPage { rows, has_next } = db
+		.leaderboard(
+			server_id,
+			view.emote,
+			view.order,
+			view.period,
+			offset,
+			view.size,
+		)
+		.await?;
+
+	// rows come grouped by emote, so each emote's rows are next to each other
+	let mut emotes: Vec<(Box<str>, Vec<LeaderboardRow>)> = Vec::new();
+	for row in rows {
+		match emotes.last_mut() {
+			Some((emote, rows)) if *emote == row.emote => rows.push(row),
+			_ => emotes.push((row.emote.clone(), vec![row])),
+		}
+	}
+	let (emotes, has_next) = if all_emotes {
+		let per_page = (MAX_ALL_EMOTES_ROWS / view.size).max(1) as usize;
+		let per_page = per_page.min(MAX_EMBED_FIELDS);
+		let start = view.page as usize * per_page;
+		let has_next = emotes.len() > start + per_page;
+		let emotes = emotes.into_iter().skip(start).take(per_page).collect();
+		(emotes, has_next)
+	} else {
+		(emotes, has_next)
+	};
+
+	let title = match (view.global, view.period) {
+		(false, Period::All) => tr!(lang, "Leaderboard"),
+		(false, Period::Day) => tr!(lang, "Leaderboard (today)"),
+		(false, Period::Week) => tr!(lang, "Leaderboard (this week)"),
+		(false, Period::Month) => tr!(lang, "Leaderboard (this month)"),
+		(true, Period::All) => tr!(lang, "Global leaderboard"),
+		(true, Period::Day) => tr!(lang, "Global leaderboard (today)"),
+		(true, Period::Week) => tr!(lang, "Global leaderboard (this week)"),
+		(true, Period::Month) => tr!(lang, "Global leaderboard (this month)"),
+	};
+	let mut embed = CreateEmbed::new()
+		.title(title)
+		.footer(CreateEmbedFooter::new(tr!(
+			lang,
+			"Page {page}",
+			page = view.page + 1
+		)));
+	if emotes.is_empty() {
+		embed = embed.description(match view.period {
+			Period::All => tr!(lang, "Nobody has any x3s here yet :c"),
+			Period::Day => tr!(lang, "Nobody has any x3s here today yet :c"),
+			Period::Week => tr!(lang, "Nobody has any x3s here this week yet :c"),
+			Period::Month => tr!(lang, "Nobody has any x3s here this month yet :c"),
+		});
+	}
+
+	for (emote, rows) in emotes.into_iter().take(MAX_EMBED_FIELDS) {
+		let rows_str = rows
+			.iter()
+			.map(ToString::to_string)
+			.collect::<Box<[_]>>()
+			.join("\n");
+		let name = match view.emote {
+			Some(_) => emote,
+			None => lang.text("All emotes").into(),
+		};
+		embed = embed.field(name, rows_str, false);
+	}
+
+	let buttons = CreateActionRow::Buttons(vec![
+		CreateButton::new(view.custom_id(view.page.saturating_sub(1)))
+			.label(lang.text("Previous"))
+			.style(ButtonStyle::Secondary)
+			.disabled(view.page == 0),
+		CreateButton::new(view.custom_id(view.page + 1))
+			.label(lang.text("Next"))
+			.style(ButtonStyle::Secondary)
+			.disabled(!has_next),
+	]);
+
+	Ok(CreateInteractionResponseMessage::new()
+		.embed(embed)
+		.components(vec![buttons])
+		.allowed_mentions(CreateAllowedMentions::new()))
+}
+
+/// Handles the Previous and Next buttons of both leaderboards
+pub async fn leaderboard_page(
+	db: &dyn CounterStore,
+	component: &ComponentInteraction,
+) -> sqlx::Result<Option<CreateInteractionResponseMessage>> {
+	let Some(view) = LeaderboardView::parse(&component.data.custom_id) else {
+		return Ok(None);
+	};
+	let server_id = match (view.global, component.guild_id) {
+		(true, _) => None,
+		(false, Some(server_id)) => Some(server_id.get()),
+		(false, None) => return Ok(None),
+	};
+	let lang = Language::from_locale(&component.locale);
+	leaderboard_message(db, lang, server_id, &view)
+		.await
+		.map(Some)
+}
+
+/// Suggests the default emotes and every emote, plus the server's own when used in a server with a database
+pub async fn leaderboard_autocomplete(
+	db: Option<&DatabaseHandler>,
+	cmd: &CommandInteraction,
+) -> sqlx::Result<CreateAutocompleteResponse> {
+	emote_autocomplete(db, cmd, true).await
+}
+/// Suggests only emotes that are counted, since a count can't be set for every emote at once
+pub async fn admin_autocomplete(
+	db: Option<&DatabaseHandler>,
+	cmd: &CommandInteraction,
+) -> sqlx::Result<CreateAutocompleteResponse> {
+	emote_autocomplete(db, cmd, false).await
+}
+async fn emote_autocomplete(
+	db: Option<&DatabaseHandler>,
+	cmd: &CommandInteraction,
+	any_emote: bool,
+) -> sqlx::Result<CreateAutocompleteResponse> {
+	let typed = cmd
+		.data
+		.autocomplete()
+		.map(|option| option.value.to_lowercase())
+		.unwrap_or_default();
+
+	let (patterns, aliases) = match (db, cmd.guild_id) {
+		(Some(db), Some(server_id)) if cmd.data.name != GlobalLeaderboardCommand::NAME => (
+			db.server_patterns(server_id.get()).await?,
+			db.emote_aliases(server_id.get()).await?,
+		),
+		_ => Default::default(),
+	};
+	let choices = include_str!("emotes.txt")
+		.lines()
+		.map(str::to_owned)
+		.chain(patterns.into_iter().map(|p| p.emote.into_string()))
+		.filter(|emote| any_emote || emote != ANY_EMOTE)
+		.filter(|emote| !aliases.iter().any(|a| *a.alias == **emote))
+		.filter(|emote| emote_label(emote).to_lowercase().contains(&typed))
+		.take(25)
+		.fold(CreateAutocompleteResponse::new(), |res, emote| {
+			res.add_string_choice(emote_label(&emote).into_owned(), emote)
+		});
+	Ok(choices)
 }
 
 command!(
@@ -277,31 +989,49 @@ command!(
 	[REPEAT_ARG]
 );
 pub async fn mute_all(
-	db: &DatabaseHandler,
+	db: &dyn CounterStore,
 	cmd: &CommandInteraction,
 ) -> sqlx::Result<CreateInteractionResponseMessage> {
+	let lang = Language::from_locale(&cmd.locale);
 	let Some(server_id) = cmd.guild_id else {
-		response!(server error);
+		response!(server error, lang);
 	};
 	let server_id = server_id.get();
 
 	let content = match cmd.data.options.as_slice() {
 		[] => {
-			db.mute_all(server_id, Some(0)).await?;
-			"I won't respond to messages in this server but will still count x3s".to_owned()
+			let old = db.mute_all(server_id, Some(0)).await?;
+			let change = change(
+				"mute_all",
+				None,
+				Some(silent_value(old)),
+				Some(silent_value(Some(0))),
+			);
+			db.audit(cmd.user.id.get(), server_id, change).await?;
+			tr!(
+				lang,
+				"I won't respond to messages in this server but will still count x3s"
+			)
 		}
 		[arg, ..] => {
 			if let CommandDataOptionValue::Integer(count) = arg.value {
-				db.mute_all(server_id, Some(count as u32)).await?;
+				let old = db.mute_all(server_id, Some(count as u32)).await?;
+				let change = change(
+					"mute_all",
+					None,
+					Some(silent_value(old)),
+					Some(silent_value(Some(count as u32))),
+				);
+				db.audit(cmd.user.id.get(), server_id, change).await?;
 
-				format!(
-					"I will only respond to every {}{} x3 in this server",
-					count,
-					postfix(count)
+				tr!(
+					lang,
+					"I will only respond to every {nth} x3 in this server",
+					nth = lang.ordinal(count)
 				)
 			} else {
 				eprintln!("Argument {} has incorrect type", arg.name);
-				response!(argument error);
+				response!(argument error, lang);
 			}
 		}
 	};
@@ -317,14 +1047,1345 @@ command!(
 	Permissions::MANAGE_MESSAGES
 );
 pub async fn unmute_all(
+	db: &dyn CounterStore,
+	cmd: &CommandInteraction,
+) -> sqlx::Result<CreateInteractionResponseMessage> {
+	let lang = Language::from_locale(&cmd.locale);
+	let Some(server_id) = cmd.guild_id else {
+		response!(server error, lang);
+	};
+	let server_id = server_id.get();
+
+	let old = db.mute_all(server_id, None).await?;
+	let change = change(
+		"mute_all",
+		None,
+		Some(silent_value(old)),
+		Some(silent_value(None)),
+	);
+	db.audit(cmd.user.id.get(), server_id, change).await?;
+	response!(tr!(lang, "I will now respond to messages in this server"))
+}
+
+/// Adds the pattern unless the server is at its limit or the patterns don't compile together,
+/// returns the reason if it isn't added
+async fn add_server_pattern(
+	db: &DatabaseHandler,
+	lang: Language,
+	actor_id: u64,
+	server_id: u64,
+	pattern: &ServerPattern,
+) -> sqlx::Result<Option<String>> {
+	let patterns = db.server_patterns(server_id).await?;
+	if patterns.len() >= MAX_SERVER_PATTERNS && !patterns.iter().any(|p| p.emote == pattern.emote) {
+		return Ok(Some(tr!(
+			lang,
+			"This server already has {max} custom emotes",
+			max = MAX_SERVER_PATTERNS
+		)));
+	}
+	// every pattern of the server ends up in one regex, which can get too big
+	let mut combined: Vec<_> = patterns
+		.into_iter()
+		.filter(|p| p.emote != pattern.emote)
+		.collect();
+	combined.push(pattern.clone());
+	if let Err(why) = Matcher::new(&combined, &[], db.match_mode(server_id).await?) {
+		return Ok(Some(tr!(
+			lang,
+			"This pattern is invalid:\n```\n{why}\n```",
+			why = why
+		)));
+	}
+	let old = db.add_pattern(server_id, pattern).await?;
+	let change = change(
+		"pattern",
+		Some(format!("*{}*", pattern.emote)),
+		old,
+		Some(pattern.pattern.to_string()),
+	);
+	db.audit(actor_id, server_id, change).await?;
+	Ok(None)
+}
+
+command!(
+	PatternCommand,
+	"pattern",
+	"Manage the emotes counted in this server",
+	false,
+	Permissions::MANAGE_GUILD,
+	[
+		PATTERN_ADD_ARG,
+		PATTERN_EMOJI_ARG,
+		PATTERN_REMOVE_ARG,
+		PATTERN_LIST_ARG
+	]
+);
+pub async fn pattern(
 	db: &DatabaseHandler,
 	cmd: &CommandInteraction,
 ) -> sqlx::Result<CreateInteractionResponseMessage> {
+	let lang = Language::from_locale(&cmd.locale);
 	let Some(server_id) = cmd.guild_id else {
-		response!(server error);
+		response!(server error, lang);
 	};
 	let server_id = server_id.get();
 
-	db.mute_all(server_id, None).await?;
-	response!("I will now respond to messages in this server")
+	let Some(CommandDataOption {
+		name,
+		value: CommandDataOptionValue::SubCommand(args),
+		..
+	}) = cmd.data.options.first()
+	else {
+		eprintln!("Pattern command requires a subcommand");
+		response!(argument error, lang);
+	};
+
+	let content = match name.as_str() {
+		"add" => {
+			let (Some(emote), Some(pattern)) =
+				(string_arg(args, "emote"), string_arg(args, "pattern"))
+			else {
+				eprintln!("Pattern add requires an emote and a pattern");
+				response!(argument error, lang);
+			};
+			let emote = emote.trim();
+
+			if emote.is_empty() || emote.chars().count() > MAX_EMOTE_LENGTH {
+				tr!(
+					lang,
+					"The emote name must be between 1 and {max} characters long",
+					max = MAX_EMOTE_LENGTH
+				)
+			} else if pattern.chars().count() > MAX_PATTERN_LENGTH {
+				tr!(
+					lang,
+					"The pattern must be at most {max} characters long",
+					max = MAX_PATTERN_LENGTH
+				)
+			} else if let Err(why) = Matcher::validate(pattern) {
+				tr!(lang, "This pattern is invalid:\n```\n{why}\n```", why = why)
+			} else {
+				let pattern = ServerPattern {
+					emote: emote.into(),
+					pattern: pattern.into(),
+					kind: PatternKind::Regex,
+				};
+				match add_server_pattern(db, lang, cmd.user.id.get(), server_id, &pattern).await? {
+					Some(limit) => limit,
+					None => tr!(
+						lang,
+						"I will now count `{pattern}` as *{emote}*",
+						pattern = pattern.pattern,
+						emote = emote
+					),
+				}
+			}
+		}
+		"emoji" => {
+			let Some(emoji) = string_arg(args, "emoji") else {
+				eprintln!("Pattern emoji requires an emoji");
+				response!(argument error, lang);
+			};
+
+			match ServerPattern::from_emoji(emoji) {
+				None => tr!(lang, "This is not a single custom or unicode emoji"),
+				Some(pattern) => {
+					match add_server_pattern(db, lang, cmd.user.id.get(), server_id, &pattern)
+						.await?
+					{
+						Some(limit) => limit,
+						None => tr!(lang, "I will now count {emoji}", emoji = pattern.emote),
+					}
+				}
+			}
+		}
+		"remove" => {
+			let Some(emote) = string_arg(args, "emote") else {
+				eprintln!("Pattern remove requires an emote");
+				response!(argument error, lang);
+			};
+			let emote = emote.trim();
+
+			if let Some(old) = db.remove_pattern(server_id, emote).await? {
+				let change = change("pattern", Some(format!("*{emote}*")), Some(old), None);
+				db.audit(cmd.user.id.get(), server_id, change).await?;
+				tr!(
+					lang,
+					"I won't count *{emote}* in this server anymore",
+					emote = emote
+				)
+			} else {
+				tr!(
+					lang,
+					"*{emote}* is not a custom emote in this server",
+					emote = emote
+				)
+			}
+		}
+		"list" => {
+			let patterns = db.server_patterns(server_id).await?;
+			if patterns.is_empty() {
+				tr!(lang, "This server doesn't have any custom emotes")
+			} else {
+				let patterns_str = patterns
+					.iter()
+					.map(|p| match p.kind {
+						PatternKind::Regex => format!("*{}* - `{}`", p.emote, p.pattern),
+						PatternKind::Custom | PatternKind::Unicode => {
+							format!("{} - {}", p.emote, lang.text("emoji"))
+						}
+					})
+					.collect::<Box<[_]>>()
+					.join("\n");
+				tr!(
+					lang,
+					"Here are the custom emotes in this server:\n{patterns}",
+					patterns = patterns_str
+				)
+			}
+		}
+		other => {
+			eprintln!("Unknown pattern subcommand {other}");
+			response!(argument error, lang);
+		}
+	};
+
+	response!(content)
+}
+
+command!(
+	MatchModeCommand,
+	"match_mode",
+	"Choose which emotes in a message are counted in the server",
+	false,
+	Permissions::MANAGE_GUILD,
+	[MATCH_MODE_ARG]
+);
+pub async fn match_mode(
+	db: &DatabaseHandler,
+	cmd: &CommandInteraction,
+) -> sqlx::Result<CreateInteractionResponseMessage> {
+	let lang = Language::from_locale(&cmd.locale);
+	let Some(server_id) = cmd.guild_id else {
+		response!(server error, lang);
+	};
+	let server_id = server_id.get();
+
+	let Some(mode) =
+		string_arg(&cmd.data.options, MATCH_MODE_ARG.base.name).and_then(MatchMode::from_choice)
+	else {
+		eprintln!("Match mode command requires a valid mode");
+		response!(argument error, lang);
+	};
+
+	let old = db.set_match_mode(server_id, mode).await?;
+	let change = change(
+		"match_mode",
+		None,
+		Some(old.as_str().to_owned()),
+		Some(mode.as_str().to_owned()),
+	);
+	db.audit(cmd.user.id.get(), server_id, change).await?;
+	response!(match mode {
+		MatchMode::EndOnly => tr!(lang, "I will only count the emote a message ends with"),
+		MatchMode::Anywhere => tr!(lang, "I will count the first emote anywhere in a message"),
+		MatchMode::AllOccurrences => tr!(lang, "I will count every emote in a message"),
+	})
+}
+
+command!(
+	AliasCommand,
+	"alias",
+	"Manage which emotes count as the same one in this server",
+	false,
+	Permissions::MANAGE_GUILD,
+	[ALIAS_ADD_ARG, ALIAS_REMOVE_ARG, ALIAS_LIST_ARG]
+);
+pub async fn alias(
+	db: &DatabaseHandler,
+	cmd: &CommandInteraction,
+) -> sqlx::Result<CreateInteractionResponseMessage> {
+	let lang = Language::from_locale(&cmd.locale);
+	let Some(server_id) = cmd.guild_id else {
+		response!(server error, lang);
+	};
+	let server_id = server_id.get();
+
+	let Some(CommandDataOption {
+		name,
+		value: CommandDataOptionValue::SubCommand(args),
+		..
+	}) = cmd.data.options.first()
+	else {
+		eprintln!("Alias command requires a subcommand");
+		response!(argument error, lang);
+	};
+
+	let content = match name.as_str() {
+		"add" => {
+			let (Some(variant), Some(emote)) =
+				(string_arg(args, "variant"), string_arg(args, "emote"))
+			else {
+				eprintln!("Alias add requires a variant and an emote");
+				response!(argument error, lang);
+			};
+			let (variant, emote) = (variant.trim(), emote.trim());
+
+			let aliases = db.emote_aliases(server_id).await?;
+			// point at the canonical emote so aliases never chain
+			let emote = aliases
+				.iter()
+				.find(|a| &*a.alias == emote)
+				.map_or(emote, |a| &a.emote);
+
+			if [variant, emote]
+				.iter()
+				.any(|e| e.is_empty() || e.chars().count() > MAX_EMOTE_LENGTH)
+			{
+				tr!(
+					lang,
+					"Emotes must be between 1 and {max} characters long",
+					max = MAX_EMOTE_LENGTH
+				)
+			} else if variant == emote {
+				tr!(
+					lang,
+					"*{variant}* already counts as *{emote}*",
+					variant = variant,
+					emote = emote
+				)
+			} else if aliases.len() >= MAX_SERVER_ALIASES
+				&& !aliases.iter().any(|a| &*a.alias == variant)
+			{
+				tr!(
+					lang,
+					"This server already has {max} aliases",
+					max = MAX_SERVER_ALIASES
+				)
+			} else {
+				let old = db.add_alias(server_id, variant, emote).await?;
+				let change = change(
+					"alias",
+					Some(format!("*{variant}*")),
+					old,
+					Some(emote.to_owned()),
+				);
+				db.audit(cmd.user.id.get(), server_id, change).await?;
+				tr!(
+					lang,
+					"*{variant}* now counts as *{emote}*, existing counts were merged",
+					variant = variant,
+					emote = emote
+				)
+			}
+		}
+		"remove" => {
+			let Some(variant) = string_arg(args, "variant") else {
+				eprintln!("Alias remove requires a variant");
+				response!(argument error, lang);
+			};
+			let variant = variant.trim();
+
+			if let Some(old) = db.remove_alias(server_id, variant).await? {
+				let change = change("alias", Some(format!("*{variant}*")), Some(old), None);
+				db.audit(cmd.user.id.get(), server_id, change).await?;
+				tr!(
+					lang,
+					"*{variant}* counts as its own emote again, merged counts stay merged",
+					variant = variant
+				)
+			} else {
+				tr!(
+					lang,
+					"*{variant}* is not an alias in this server",
+					variant = variant
+				)
+			}
+		}
+		"list" => {
+			let aliases = db.emote_aliases(server_id).await?;
+			if aliases.is_empty() {
+				tr!(lang, "This server doesn't have any emote aliases")
+			} else {
+				let aliases_str = aliases
+					.iter()
+					.map(|a| format!("*{}* → *{}*", a.alias, a.emote))
+					.collect::<Box<[_]>>()
+					.join("\n");
+				tr!(
+					lang,
+					"Here are the emote aliases in this server:\n{aliases}",
+					aliases = aliases_str
+				)
+			}
+		}
+		other => {
+			eprintln!("Unknown alias subcommand {other}");
+			response!(argument error, lang);
+		}
+	};
+
+	response!(content)
+}
+
+/// Sets the channel's mode and records the change
+async fn set_channel_mode(
+	db: &DatabaseHandler,
+	actor_id: u64,
+	server_id: u64,
+	channel_id: u64,
+	mode: Option<ChannelMode>,
+) -> sqlx::Result<()> {
+	let old = db.set_channel_mode(server_id, channel_id, mode).await?;
+	let change = change(
+		"channel",
+		Some(format!("<#{channel_id}>")),
+		old.map(|old| old.as_str().to_owned()),
+		mode.map(|mode| mode.as_str().to_owned()),
+	);
+	db.audit(actor_id, server_id, change).await
+}
+
+command!(
+	ChannelCommand,
+	"channel",
+	"Manage which channels x3s are counted in",
+	false,
+	Permissions::MANAGE_GUILD,
+	[
+		CHANNEL_BLOCK_ARG,
+		CHANNEL_ALLOW_ARG,
+		CHANNEL_RESET_ARG,
+		CHANNEL_LIST_ARG
+	]
+);
+pub async fn channel(
+	db: &DatabaseHandler,
+	cmd: &CommandInteraction,
+) -> sqlx::Result<CreateInteractionResponseMessage> {
+	let lang = Language::from_locale(&cmd.locale);
+	let Some(server_id) = cmd.guild_id else {
+		response!(server error, lang);
+	};
+	let server_id = server_id.get();
+
+	let Some(CommandDataOption {
+		name,
+		value: CommandDataOptionValue::SubCommand(args),
+		..
+	}) = cmd.data.options.first()
+	else {
+		eprintln!("Channel command requires a subcommand");
+		response!(argument error, lang);
+	};
+
+	let actor_id = cmd.user.id.get();
+	let channel_id = channel_arg(args, CHANNEL_ARG.base.name);
+	let content = match (name.as_str(), channel_id) {
+		("block", Some(channel_id)) => {
+			set_channel_mode(
+				db,
+				actor_id,
+				server_id,
+				channel_id,
+				Some(ChannelMode::Block),
+			)
+			.await?;
+			tr!(
+				lang,
+				"I won't count x3s in <#{channel}> and its threads anymore",
+				channel = channel_id
+			)
+		}
+		("allow", Some(channel_id)) => {
+			set_channel_mode(
+				db,
+				actor_id,
+				server_id,
+				channel_id,
+				Some(ChannelMode::Allow),
+			)
+			.await?;
+			tr!(
+				lang,
+				"I will count x3s in <#{channel}> and its threads, but only in allowed channels",
+				channel = channel_id
+			)
+		}
+		("reset", Some(channel_id)) => {
+			set_channel_mode(db, actor_id, server_id, channel_id, None).await?;
+			tr!(
+				lang,
+				"<#{channel}> is neither allowed nor blocked now",
+				channel = channel_id
+			)
+		}
+		("list", _) => {
+			let options = db.channel_options(server_id).await?;
+			let channels = |mode| {
+				options
+					.iter()
+					.filter(|o| o.mode == mode)
+					.map(|o| format!("<#{}>", o.channel_id))
+					.collect::<Box<[_]>>()
+					.join(", ")
+			};
+			let (allowed, blocked) = (channels(ChannelMode::Allow), channels(ChannelMode::Block));
+			match (allowed.is_empty(), blocked.is_empty()) {
+				(true, true) => tr!(lang, "I count x3s in every channel"),
+				(true, false) => tr!(
+					lang,
+					"I count x3s in every channel except {blocked}",
+					blocked = blocked
+				),
+				(false, true) => tr!(lang, "I only count x3s in {allowed}", allowed = allowed),
+				(false, false) => tr!(
+					lang,
+					"I only count x3s in {allowed}, and never in {blocked}",
+					allowed = allowed,
+					blocked = blocked
+				),
+			}
+		}
+		(other, _) => {
+			eprintln!("Channel {other} requires a channel");
+			response!(argument error, lang);
+		}
+	};
+
+	response!(content)
+}
+
+command!(
+	ReplyTemplateCommand,
+	"reply_template",
+	"Customise the reply to counted x3s",
+	false,
+	Permissions::MANAGE_GUILD,
+	[
+		TEMPLATE_SET_ARG,
+		TEMPLATE_RESET_ARG,
+		TEMPLATE_LIST_ARG,
+		TEMPLATE_PREVIEW_ARG
+	]
+);
+pub async fn reply_template(
+	db: &DatabaseHandler,
+	cmd: &CommandInteraction,
+) -> sqlx::Result<CreateInteractionResponseMessage> {
+	let lang = Language::from_locale(&cmd.locale);
+	let Some(server_id) = cmd.guild_id else {
+		response!(server error, lang);
+	};
+	let server_id = server_id.get();
+
+	let Some(CommandDataOption {
+		name,
+		value: CommandDataOptionValue::SubCommand(args),
+		..
+	}) = cmd.data.options.first()
+	else {
+		eprintln!("Reply template command requires a subcommand");
+		response!(argument error, lang);
+	};
+
+	let emote = string_arg(args, TEMPLATE_EMOTE_ARG.base.name).map(str::trim);
+
+	let content = match name.as_str() {
+		"set" => {
+			let Some(template) = string_arg(args, TEMPLATE_ARG.base.name) else {
+				eprintln!("Reply template set requires a template");
+				response!(argument error, lang);
+			};
+			let template = template.trim();
+
+			if emote.is_some_and(|e| e.is_empty() || e.chars().count() > MAX_EMOTE_LENGTH) {
+				tr!(
+					lang,
+					"Emotes must be between 1 and {max} characters long",
+					max = MAX_EMOTE_LENGTH
+				)
+			} else if let Err(why) = ReplyTemplate::validate(template) {
+				tr!(
+					lang,
+					"That template is invalid: {why}",
+					why = why.message(lang)
+				)
+			} else {
+				let old = db
+					.set_reply_template(server_id, emote.unwrap_or(ANY_EMOTE), template)
+					.await?;
+				let change = change(
+					"reply_template",
+					Some(format!("*{}*", emote.unwrap_or(ANY_EMOTE))),
+					old,
+					Some(template.to_owned()),
+				);
+				db.audit(cmd.user.id.get(), server_id, change).await?;
+				match emote {
+					Some(emote) => tr!(
+						lang,
+						"I will reply to *{emote}* with:\n{template}",
+						emote = emote,
+						template = template
+					),
+					None => tr!(
+						lang,
+						"I will reply to every emote with:\n{template}",
+						template = template
+					),
+				}
+			}
+		}
+		"reset" => {
+			if let Some(old) = db
+				.remove_reply_template(server_id, emote.unwrap_or(ANY_EMOTE))
+				.await?
+			{
+				let change = change(
+					"reply_template",
+					Some(format!("*{}*", emote.unwrap_or(ANY_EMOTE))),
+					Some(old),
+					None,
+				);
+				db.audit(cmd.user.id.get(), server_id, change).await?;
+				match emote {
+					Some(emote) => tr!(
+						lang,
+						"I will reply to *{emote}* with the default reply again",
+						emote = emote
+					),
+					None => tr!(
+						lang,
+						"I will reply to every emote with the default reply again"
+					),
+				}
+			} else {
+				match emote {
+					Some(emote) => tr!(
+						lang,
+						"There is no reply template for *{emote}*",
+						emote = emote
+					),
+					None => tr!(lang, "There is no reply template for all emotes"),
+				}
+			}
+		}
+		"list" => {
+			let templates = db.reply_templates(server_id).await?;
+			if templates.is_empty() {
+				tr!(lang, "This server uses the default reply")
+			} else {
+				let templates_str = templates
+					.iter()
+					.map(|t| match &*t.emote {
+						ANY_EMOTE => format!("{}: {}", lang.text("Every emote"), t.template),
+						emote => format!("*{emote}*: {}", t.template),
+					})
+					.collect::<Box<[_]>>()
+					.join("\n");
+				tr!(
+					lang,
+					"Here are the reply templates in this server:\n{templates}",
+					templates = templates_str
+				)
+			}
+		}
+		"preview" => {
+			let template = match string_arg(args, TEMPLATE_PREVIEW_TEMPLATE_ARG.base.name) {
+				Some(template) => Some(template.trim().to_owned()),
+				None => {
+					let templates = db.reply_templates(server_id).await?;
+					templates
+						.iter()
+						.find(|t| Some(&*t.emote) == emote)
+						.or_else(|| templates.iter().find(|t| &*t.emote == ANY_EMOTE))
+						.map(|t| t.template.to_string())
+				}
+			};
+			match template {
+				None => tr!(lang, "This server uses the default reply"),
+				Some(template) => match ReplyTemplate::validate(&template) {
+					Err(why) => tr!(
+						lang,
+						"That template is invalid: {why}",
+						why = why.message(lang)
+					),
+					Ok(()) => render(
+						&template,
+						TemplateValues {
+							user_id: cmd.user.id.get(),
+							emote: emote.unwrap_or(":3"),
+							count: 42,
+							rank: Some(1),
+							lang,
+						},
+					),
+				},
+			}
+		}
+		other => {
+			eprintln!("Unknown reply template subcommand {other}");
+			response!(argument error, lang);
+		}
+	};
+
+	response!(content)
+}
+
+command!(
+	LanguageCommand,
+	"language",
+	"Choose the language of the replies to counted x3s in the server",
+	false,
+	Permissions::MANAGE_GUILD,
+	[LANGUAGE_ARG]
+);
+pub async fn language(
+	db: &DatabaseHandler,
+	cmd: &CommandInteraction,
+) -> sqlx::Result<CreateInteractionResponseMessage> {
+	let lang = Language::from_locale(&cmd.locale);
+	let Some(server_id) = cmd.guild_id else {
+		response!(server error, lang);
+	};
+	let server_id = server_id.get();
+
+	let Some(language) =
+		string_arg(&cmd.data.options, LANGUAGE_ARG.base.name).and_then(Language::from_choice)
+	else {
+		eprintln!("Language command requires a valid language");
+		response!(argument error, lang);
+	};
+
+	let old = db.set_language(server_id, language).await?;
+	let change = change(
+		"language",
+		None,
+		Some(old.as_str().to_owned()),
+		Some(language.as_str().to_owned()),
+	);
+	db.audit(cmd.user.id.get(), server_id, change).await?;
+	response!(tr!(
+		language,
+		"I will reply to counted x3s in English in this server"
+	))
+}
+
+/// Quotes every field so emotes with commas or quotes stay in their column
+fn csv<const N: usize>(header: [&str; N], rows: impl Iterator<Item = [String; N]>) -> String {
+	let line = |fields: &mut dyn Iterator<Item = &str>| {
+		fields
+			.map(|field| format!("\"{}\"", field.replace('"', "\"\"")))
+			.collect::<Box<[_]>>()
+			.join(",")
+	};
+	let mut csv = line(&mut header.into_iter());
+	for row in rows {
+		csv.push_str("\r\n");
+		csv.push_str(&line(&mut row.iter().map(String::as_str)));
+	}
+	csv
+}
+
+command!(
+	ExportMyDataCommand,
+	"export_my_data",
+	"Get everything I store about you"
+);
+pub async fn export_my_data(
+	db: &DatabaseHandler,
+	cmd: &CommandInteraction,
+) -> sqlx::Result<CreateInteractionResponseMessage> {
+	let lang = Language::from_locale(&cmd.locale);
+	let data = db.user_data(cmd.user.id.get()).await?;
+
+	let json = match serde_json::to_vec_pretty(&data) {
+		Ok(json) => json,
+		Err(why) => {
+			eprintln!("Could not serialize user data: {why}");
+			return response!(tr!(
+				lang,
+				"I couldn't export your data, please try again later"
+			));
+		}
+	};
+	let counts = csv(
+		["server_id", "emote", "count"],
+		data.counts.iter().map(|c| {
+			[
+				c.server_id.to_string(),
+				c.emote.clone(),
+				c.count.to_string(),
+			]
+		}),
+	);
+	let events = csv(
+		[
+			"server_id",
+			"channel_id",
+			"message_id",
+			"reply_id",
+			"emote",
+			"variant",
+			"matched",
+			"created_at",
+		],
+		data.events.iter().map(|e| {
+			[
+				e.server_id.to_string(),
+				e.channel_id.to_string(),
+				e.message_id.to_string(),
+				e.reply_id.map(|id| id.to_string()).unwrap_or_default(),
+				e.emote.clone(),
+				e.variant.clone(),
+				e.matched.clone(),
+				e.created_at.clone(),
+			]
+		}),
+	);
+
+	Ok(CreateInteractionResponseMessage::new()
+		.content(tr!(lang, "Here is everything I store about you"))
+		.add_file(CreateAttachment::bytes(json, "x3_data.json"))
+		.add_file(CreateAttachment::bytes(counts, "x3_counts.csv"))
+		.add_file(CreateAttachment::bytes(events, "x3_events.csv"))
+		.ephemeral(true)
+		.allowed_mentions(CreateAllowedMentions::new()))
+}
+
+/// The bot owner set in `OWNER_ID`, who can delete anyone's data everywhere
+fn is_owner(user_id: u64) -> bool {
+	env::var("OWNER_ID")
+		.ok()
+		.and_then(|id| id.trim().parse().ok())
+		== Some(user_id)
+}
+
+/// Asks for confirmation with a button encoding who deletes whose data where
+fn delete_data_message(
+	lang: Language,
+	content: String,
+	actor_id: u64,
+	user_id: u64,
+	server_id: Option<u64>,
+) -> CreateInteractionResponseMessage {
+	let custom_id = format!(
+		"{DELETE_DATA_ID}:{actor_id}:{user_id}:{}",
+		server_id.map(|id| id.to_string()).unwrap_or_default()
+	);
+	CreateInteractionResponseMessage::new()
+		.content(content)
+		.components(vec![CreateActionRow::Buttons(vec![CreateButton::new(
+			custom_id,
+		)
+		.label(lang.text("Delete"))
+		.style(ButtonStyle::Danger)])])
+		.ephemeral(true)
+		.allowed_mentions(CreateAllowedMentions::new())
+}
+
+command!(
+	DeleteMyDataCommand,
+	"delete_my_data",
+	"Delete everything I store about you"
+);
+pub async fn delete_my_data(
+	_db: &DatabaseHandler,
+	cmd: &CommandInteraction,
+) -> sqlx::Result<CreateInteractionResponseMessage> {
+	let lang = Language::from_locale(&cmd.locale);
+	let user_id = cmd.user.id.get();
+
+	Ok(delete_data_message(
+		lang,
+		tr!(
+			lang,
+			"This deletes your counts, their history and your settings in every server, which can't be undone. You stay opted out afterwards, run /opt_in if you want to be counted again"
+		),
+		user_id,
+		user_id,
+		None,
+	))
+}
+
+command!(
+	DeleteUserDataCommand,
+	"delete_user_data",
+	"Delete everything I store about a user who asked for it",
+	true,
+	Permissions::MANAGE_GUILD,
+	[DELETE_USER_ARG, DELETE_SCOPE_ARG]
+);
+pub async fn delete_user_data(
+	_db: &DatabaseHandler,
+	cmd: &CommandInteraction,
+) -> sqlx::Result<CreateInteractionResponseMessage> {
+	let lang = Language::from_locale(&cmd.locale);
+	let actor_id = cmd.user.id.get();
+
+	let Some(user_id) = cmd
+		.data
+		.options
+		.iter()
+		.find(|arg| arg.name == DELETE_USER_ARG.base.name)
+		.and_then(|arg| arg.value.as_user_id())
+	else {
+		eprintln!("Delete user data requires a user");
+		response!(argument error, lang);
+	};
+	let user_id = user_id.get();
+
+	let (content, server_id) = match string_arg(&cmd.data.options, DELETE_SCOPE_ARG.base.name) {
+		Some("global") => {
+			if !is_owner(actor_id) {
+				return response!(tr!(
+					lang,
+					"Only the owner of the bot can delete someone's data everywhere"
+				));
+			}
+			let content = tr!(
+				lang,
+				"This deletes <@{user}>'s counts, their history and their settings in every server, which can't be undone",
+				user = user_id
+			);
+			(content, None)
+		}
+		_ => {
+			let Some(server_id) = cmd.guild_id else {
+				response!(server error, lang);
+			};
+			let content = tr!(
+				lang,
+				"This deletes <@{user}>'s counts, their history and their settings in this server, which can't be undone",
+				user = user_id
+			);
+			(content, Some(server_id.get()))
+		}
+	};
+
+	Ok(delete_data_message(
+		lang, content, actor_id, user_id, server_id,
+	))
+}
+
+/// Handles the confirmation button of both data deletion commands
+pub async fn delete_data_confirm(
+	db: &DatabaseHandler,
+	component: &ComponentInteraction,
+) -> sqlx::Result<Option<CreateInteractionResponseMessage>> {
+	let mut parts = component.data.custom_id.splitn(4, ':');
+	if parts.next() != Some(DELETE_DATA_ID) {
+		return Ok(None);
+	}
+	let (Some(Ok(actor_id)), Some(Ok(user_id)), Some(server_id)) = (
+		parts.next().map(str::parse::<u64>),
+		parts.next().map(str::parse::<u64>),
+		parts.next(),
+	) else {
+		return Ok(None);
+	};
+	let server_id = server_id.parse::<u64>().ok();
+
+	// the permissions are checked again since only the actor should be able to click
+	let is_admin = component
+		.member
+		.as_ref()
+		.and_then(|member| member.permissions)
+		.is_some_and(|permissions| permissions.manage_guild());
+	let allowed = component.user.id.get() == actor_id
+		&& match server_id {
+			Some(server_id) => {
+				is_admin && component.guild_id.is_some_and(|id| id.get() == server_id)
+			}
+			None => actor_id == user_id || is_owner(actor_id),
+		};
+	if !allowed {
+		return Ok(None);
+	}
+
+	db.delete_user_data(actor_id, user_id, server_id.map(Into::into))
+		.await?;
+
+	let lang = Language::from_locale(&component.locale);
+	let content = match (actor_id == user_id, server_id) {
+		(true, _) => tr!(lang, "Everything I stored about you was deleted"),
+		(false, Some(_)) => tr!(
+			lang,
+			"Everything I stored about <@{user}> in this server was deleted",
+			user = user_id
+		),
+		(false, None) => tr!(
+			lang,
+			"Everything I stored about <@{user}> was deleted",
+			user = user_id
+		),
+	};
+	Ok(Some(
+		CreateInteractionResponseMessage::new()
+			.content(content)
+			.components(Vec::new())
+			.allowed_mentions(CreateAllowedMentions::new()),
+	))
+}
+
+command!(
+	AdminCommand,
+	"admin",
+	"Fix the counts in this server",
+	false,
+	Permissions::MANAGE_GUILD,
+	[
+		ADMIN_SET_COUNT_ARG,
+		ADMIN_RESET_USER_ARG,
+		ADMIN_RESET_SERVER_ARG
+	]
+);
+pub async fn admin(
+	db: &DatabaseHandler,
+	cmd: &CommandInteraction,
+) -> sqlx::Result<CreateInteractionResponseMessage> {
+	let lang = Language::from_locale(&cmd.locale);
+	let Some(server_id) = cmd.guild_id else {
+		response!(server error, lang);
+	};
+	let server_id = server_id.get();
+	let actor_id = cmd.user.id.get();
+
+	let Some(CommandDataOption {
+		name,
+		value: CommandDataOptionValue::SubCommand(args),
+		..
+	}) = cmd.data.options.first()
+	else {
+		eprintln!("Admin command requires a subcommand");
+		response!(argument error, lang);
+	};
+
+	let Some(reason) = string_arg(args, ADMIN_REASON_ARG.base.name).map(str::trim) else {
+		eprintln!("Admin {name} requires a reason");
+		response!(argument error, lang);
+	};
+	if reason.is_empty() || reason.chars().count() > MAX_REASON_LENGTH {
+		return response!(tr!(
+			lang,
+			"The reason must be between 1 and {max} characters long",
+			max = MAX_REASON_LENGTH
+		));
+	}
+
+	let user_id = user_arg(args, ADMIN_USER_ARG.base.name);
+	let content = match (name.as_str(), user_id) {
+		("set_count", Some(user_id)) => {
+			let (Some(emote), Some(count)) = (
+				string_arg(args, ADMIN_EMOTE_ARG.base.name).map(str::trim),
+				int_arg(args, ADMIN_COUNT_ARG.base.name),
+			) else {
+				eprintln!("Admin set_count requires an emote and a count");
+				response!(argument error, lang);
+			};
+			if emote.is_empty() || emote.chars().count() > MAX_EMOTE_LENGTH {
+				return response!(tr!(
+					lang,
+					"Emotes must be between 1 and {max} characters long",
+					max = MAX_EMOTE_LENGTH
+				));
+			}
+
+			// counts are stored under the canonical emote
+			let aliases = db.emote_aliases(server_id).await?;
+			let emote = aliases
+				.iter()
+				.find(|a| &*a.alias == emote)
+				.map_or(emote, |a| &a.emote);
+			if !db.matcher(server_id).await?.counts(emote) {
+				return response!(tr!(
+					lang,
+					"*{emote}* isn't counted in this server",
+					emote = emote
+				));
+			}
+
+			let old_count = db
+				.set_count(actor_id, server_id, user_id, emote, count as u32, reason)
+				.await?;
+			tr!(
+				lang,
+				"Set <@{user}>'s *{emote}* count from {old} to {count}",
+				user = user_id,
+				emote = emote,
+				old = old_count,
+				count = count
+			)
+		}
+		("reset_user", Some(user_id)) => {
+			let removed = db
+				.reset_counts(actor_id, server_id, Some(user_id.into()), reason)
+				.await?;
+			tr!(
+				lang,
+				"Reset <@{user}>'s counts in this server, {count} x3s were removed",
+				user = user_id,
+				count = removed
+			)
+		}
+		("reset_server", _) => {
+			let custom_id = format!("{RESET_SERVER_ID}:{actor_id}:{reason}");
+			return Ok(CreateInteractionResponseMessage::new()
+				.content(tr!(
+					lang,
+					"This removes every count in this server and their history, which can't be undone"
+				))
+				.components(vec![CreateActionRow::Buttons(vec![CreateButton::new(
+					custom_id,
+				)
+				.label(lang.text("Reset"))
+				.style(ButtonStyle::Danger)])])
+				.ephemeral(true)
+				.allowed_mentions(CreateAllowedMentions::new()));
+		}
+		(other, _) => {
+			eprintln!("Admin {other} requires a user");
+			response!(argument error, lang);
+		}
+	};
+
+	response!(content)
+}
+
+/// Handles the confirmation button of `/admin reset_server`
+pub async fn reset_server_confirm(
+	db: &DatabaseHandler,
+	component: &ComponentInteraction,
+) -> sqlx::Result<Option<CreateInteractionResponseMessage>> {
+	let mut parts = component.data.custom_id.splitn(3, ':');
+	if parts.next() != Some(RESET_SERVER_ID) {
+		return Ok(None);
+	}
+	let (Some(Ok(actor_id)), Some(reason), Some(server_id)) = (
+		parts.next().map(str::parse::<u64>),
+		parts.next(),
+		component.guild_id,
+	) else {
+		return Ok(None);
+	};
+
+	// the permissions are checked again since only the actor should be able to click
+	let is_admin = component
+		.member
+		.as_ref()
+		.and_then(|member| member.permissions)
+		.is_some_and(|permissions| permissions.manage_guild());
+	if component.user.id.get() != actor_id || !is_admin {
+		return Ok(None);
+	}
+
+	let removed = db
+		.reset_counts(actor_id, server_id.get(), None, reason)
+		.await?;
+
+	let lang = Language::from_locale(&component.locale);
+	Ok(Some(
+		CreateInteractionResponseMessage::new()
+			.content(tr!(
+				lang,
+				"Every count in this server was reset, {count} x3s were removed",
+				count = removed
+			))
+			.components(Vec::new())
+			.allowed_mentions(CreateAllowedMentions::new()),
+	))
+}
+
+/// Keeps values like templates short and in one piece of inline code
+fn audit_value(lang: Language, value: Option<&str>) -> String {
+	let Some(value) = value else {
+		return lang.text("nothing").to_owned();
+	};
+	let mut value: String = value
+		.chars()
+		.filter(|c| *c != '`')
+		.map(|c| if c == '\n' { ' ' } else { c })
+		.collect();
+	if value.chars().count() > MAX_AUDIT_VALUE_LENGTH {
+		value = value.chars().take(MAX_AUDIT_VALUE_LENGTH - 1).collect();
+		value.push('…');
+	}
+	format!("`{value}`")
+}
+
+command!(
+	AuditCommand,
+	"audit",
+	"List the latest changes to the settings and counts in this server",
+	false,
+	Permissions::MANAGE_GUILD
+);
+pub async fn audit(
+	db: &DatabaseHandler,
+	cmd: &CommandInteraction,
+) -> sqlx::Result<CreateInteractionResponseMessage> {
+	let lang = Language::from_locale(&cmd.locale);
+	let Some(server_id) = cmd.guild_id else {
+		response!(server error, lang);
+	};
+
+	let entries = db.audit_log(server_id.get(), AUDIT_LOG_ENTRIES).await?;
+	let description = if entries.is_empty() {
+		tr!(lang, "Nothing was changed in this server yet")
+	} else {
+		entries
+			.iter()
+			.map(|entry| {
+				let actor = match entry.actor_id {
+					Some(actor_id) => format!("<@{actor_id}>"),
+					None => lang.text("A deleted user").to_owned(),
+				};
+				let mut line = format!("<t:{}:R> {actor} **{}**", entry.created_at, entry.action);
+				if let Some(user_id) = entry.user_id.filter(|id| Some(*id) != entry.actor_id) {
+					line.push_str(&format!(" <@{user_id}>"));
+				}
+				if let Some(target) = &entry.target {
+					line.push(' ');
+					line.push_str(target);
+				}
+				line.push_str(&format!(
+					": {} → {}",
+					audit_value(lang, entry.old_value.as_deref()),
+					audit_value(lang, entry.new_value.as_deref())
+				));
+				if let Some(reason) = &entry.reason {
+					line.push_str(&format!(" ({})", audit_value(lang, Some(reason))));
+				}
+				line
+			})
+			.collect::<Box<[_]>>()
+			.join("\n")
+	};
+
+	Ok(CreateInteractionResponseMessage::new()
+		.embed(
+			CreateEmbed::new()
+				.title(tr!(lang, "Audit log"))
+				.description(description),
+		)
+		.ephemeral(true)
+		.allowed_mentions(CreateAllowedMentions::new()))
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::{json, Value};
+
+	use super::*;
+	use crate::{database::MemoryStore, matcher::Found};
+
+	const SERVER: u64 = 10;
+
+	/// The command run by user 1 in the server
+	fn command(name: &str, options: Value) -> CommandInteraction {
+		serde_json::from_value(json!({
+			"id": "1",
+			"application_id": "2",
+			"data": { "id": "3", "name": name, "type": 1, "options": options },
+			"guild_id": SERVER.to_string(),
+			"channel_id": "4",
+			"user": { "id": "1", "username": "user", "discriminator": "0", "avatar": null },
+			"token": "",
+			"version": 1,
+			"app_permissions": null,
+			"locale": "en-US",
+			"guild_locale": null,
+			"entitlements": [],
+			"context": null,
+			"attachment_size_limit": 0,
+		}))
+		.unwrap()
+	}
+
+	fn response(message: CreateInteractionResponseMessage) -> Value {
+		serde_json::to_value(message).unwrap()
+	}
+
+	async fn count(store: &MemoryStore, user_id: u64, message_id: u64) {
+		let found = Found {
+			emote: ":3",
+			variant: ":3",
+			text: ":3",
+		};
+		store
+			.count_message(user_id, SERVER, 4, message_id, &[found])
+			.await
+			.unwrap();
+	}
+
+	#[tokio::test]
+	async fn counts_are_hidden_once_the_user_opts_out() {
+		let store = MemoryStore::default();
+		count(&store, 1, 100).await;
+		count(&store, 1, 101).await;
+
+		let cmd = command("counts", json!([]));
+		let res = response(counts(&store, &cmd).await.unwrap());
+		assert_eq!(res["content"], "Here are your counts:\n:3 - 2");
+		let cmd = command(
+			"counts",
+			json!([{ "name": "user", "type": 6, "value": "5" }]),
+		);
+		let res = response(counts(&store, &cmd).await.unwrap());
+		assert_eq!(res["content"], "This user doesn't have any x3s yet :c");
+
+		let cmd = command(
+			"opt_out",
+			json!([{ "name": "scope", "type": 3, "value": "server" }]),
+		);
+		let res = response(opt_out(&store, &cmd).await.unwrap());
+		assert_eq!(
+			res["content"],
+			"I won't count your ':3's in this server now qwq"
+		);
+		assert!(store.is_opt_out(1, Some(SERVER)).await.unwrap());
+		assert!(!store.is_opt_out(1, None).await.unwrap());
+		let res = response(counts(&store, &command("counts", json!([]))).await.unwrap());
+		assert_eq!(
+			res["content"],
+			"You opted out, so I don't count your x3s :c"
+		);
+	}
+
+	#[tokio::test]
+	async fn leaderboard_pages_through_the_ranking() {
+		let store = MemoryStore::default();
+		let mut message_id = 100;
+		for (user_id, times) in [(1, 1), (2, 3), (3, 2)] {
+			for _ in 0..times {
+				count(&store, user_id, message_id).await;
+				message_id += 1;
+			}
+		}
+
+		let cmd = command(
+			"leaderboard",
+			json!([
+				{ "name": "emote", "type": 3, "value": ":3" },
+				{ "name": "count", "type": 4, "value": 2 },
+			]),
+		);
+		let res = response(leaderboard(&store, &cmd).await.unwrap());
+		let embed = &res["embeds"][0];
+		assert_eq!(embed["title"], "Leaderboard");
+		assert_eq!(embed["fields"][0]["name"], ":3");
+		assert_eq!(embed["fields"][0]["value"], "1\\. <@2> - 3\n2\\. <@3> - 2");
+		let buttons = &res["components"][0]["components"];
+		assert_eq!(buttons[0]["disabled"], true);
+		assert_eq!(buttons[1]["disabled"], false);
+
+		let res = response(
+			leaderboard(&store, &command("leaderboard", json!([])))
+				.await
+				.unwrap(),
+		);
+		assert_eq!(res["embeds"][0]["fields"][0]["name"], "All emotes");
+	}
 }
@@ -1,10 +1,38 @@
+use std::collections::HashMap;
+
 use serenity::all::{CommandOption, CommandOptionType, CreateCommandOption};
 
+use crate::locale::{description_localizations, name_localizations};
+
 pub trait IntoCommandArg: PartialEq<CommandOption> {
 	fn name(&self) -> &str;
 	fn to_arg(&self) -> CreateCommandOption;
 }
 
+pub fn args_eq(new_args: &[&dyn IntoCommandArg], old_args: &[CommandOption]) -> bool {
+	if old_args.len() != new_args.len() {
+		return false;
+	}
+	let old_args = old_args
+		.iter()
+		.map(|arg| (arg.name.as_str(), arg))
+		.collect::<HashMap<_, _>>();
+	new_args.iter().all(|new_arg| {
+		old_args
+			.get(new_arg.name())
+			.map(|old_arg| *new_arg == *old_arg)
+			.unwrap_or(false)
+	})
+}
+
+/// Registered commands leave out the localizations when there are none
+pub fn localizations_eq(
+	old: &Option<HashMap<String, String>>,
+	new: &HashMap<String, String>,
+) -> bool {
+	old.as_ref().map_or(new.is_empty(), |old| old == new)
+}
+
 #[derive(Debug, Clone)]
 pub struct BaseArg {
 	pub name: &'static str,
@@ -17,12 +45,28 @@ impl PartialEq<CommandOption> for BaseArg {
 		other.required == self.required
 			&& other.name == self.name
 			&& other.description == self.description
+			&& localizations_eq(&other.name_localizations, &name_localizations(self.name))
+			&& localizations_eq(
+				&other.description_localizations,
+				&description_localizations(self.description),
+			)
 	}
 }
 
 impl BaseArg {
 	fn to_arg(&self, kind: CommandOptionType) -> CreateCommandOption {
-		CreateCommandOption::new(kind, self.name, self.description).required(self.required)
+		let option =
+			CreateCommandOption::new(kind, self.name, self.description).required(self.required);
+		let option = name_localizations(self.name)
+			.into_iter()
+			.fold(option, |option, (locale, name)| {
+				option.name_localized(locale, name)
+			});
+		description_localizations(self.description)
+			.into_iter()
+			.fold(option, |option, (locale, description)| {
+				option.description_localized(locale, description)
+			})
 	}
 }
 
@@ -80,10 +124,32 @@ impl IntoCommandArg for UserArg {
 	}
 }
 
+#[derive(Debug, Clone)]
+pub struct ChannelArg {
+	pub base: BaseArg,
+}
+
+impl PartialEq<CommandOption> for ChannelArg {
+	fn eq(&self, other: &CommandOption) -> bool {
+		other.kind == CommandOptionType::Channel && self.base.eq(other)
+	}
+}
+
+impl IntoCommandArg for ChannelArg {
+	fn name(&self) -> &str {
+		self.base.name
+	}
+
+	fn to_arg(&self) -> CreateCommandOption {
+		self.base.to_arg(CommandOptionType::Channel)
+	}
+}
+
 #[derive(Debug, Clone)]
 pub struct StringArg {
 	pub base: BaseArg,
 	pub choices: &'static str,
+	pub autocomplete: bool,
 }
 
 impl StringArg {
@@ -97,7 +163,10 @@ impl StringArg {
 
 impl PartialEq<CommandOption> for StringArg {
 	fn eq(&self, other: &CommandOption) -> bool {
-		if !(other.kind == CommandOptionType::String && self.base.eq(other)) {
+		if !(other.kind == CommandOptionType::String
+			&& other.autocomplete == self.autocomplete
+			&& self.base.eq(other))
+		{
 			return false;
 		}
 
@@ -114,6 +183,12 @@ impl PartialEq<CommandOption> for StringArg {
 			.into_iter()
 			.zip(new_choices)
 			.all(|(l, r)| l == r)
+			&& other.choices.iter().all(|choice| {
+				localizations_eq(
+					&choice.name_localizations,
+					&name_localizations(&choice.name),
+				)
+			})
 	}
 }
 
@@ -123,10 +198,40 @@ impl IntoCommandArg for StringArg {
 	}
 
 	fn to_arg(&self) -> CreateCommandOption {
-		let mut option = self.base.to_arg(CommandOptionType::String);
+		let mut option = self
+			.base
+			.to_arg(CommandOptionType::String)
+			.set_autocomplete(self.autocomplete);
 		for choice in self.gen_choices() {
-			option = option.add_string_choice(choice, choice);
+			option = option.add_string_choice_localized(choice, choice, name_localizations(choice));
 		}
 		option
 	}
 }
+
+#[derive(Clone)]
+pub struct SubCommandArg {
+	pub base: BaseArg,
+	pub args: &'static [&'static dyn IntoCommandArg],
+}
+
+impl PartialEq<CommandOption> for SubCommandArg {
+	fn eq(&self, other: &CommandOption) -> bool {
+		other.kind == CommandOptionType::SubCommand
+			&& self.base.eq(other)
+			&& args_eq(self.args, &other.options)
+	}
+}
+
+impl IntoCommandArg for SubCommandArg {
+	fn name(&self) -> &str {
+		self.base.name
+	}
+
+	fn to_arg(&self) -> CreateCommandOption {
+		self.args.iter().fold(
+			self.base.to_arg(CommandOptionType::SubCommand),
+			|option, arg| option.add_sub_option(arg.to_arg()),
+		)
+	}
+}
@@ -25,6 +25,26 @@ macro_rules! arg {
 			},
 		}
 	};
+	(Channel, $name:literal, $desc:literal, $required:literal) => {
+		ChannelArg {
+			base: BaseArg {
+				name: $name,
+				description: $desc,
+				required: $required,
+			},
+		}
+	};
+	(String, $name:literal, $desc:literal, $required:literal, autocomplete) => {
+		StringArg {
+			base: BaseArg {
+				name: $name,
+				description: $desc,
+				required: $required,
+			},
+			choices: "",
+			autocomplete: true,
+		}
+	};
 	(String, $name:literal, $desc:literal, $required:literal, $choices:expr) => {
 		StringArg {
 			base: BaseArg {
@@ -33,6 +53,17 @@ macro_rules! arg {
 				required: $required,
 			},
 			choices: $choices,
+			autocomplete: false,
+		}
+	};
+	(SubCommand, $name:literal, $desc:literal, [$($args:expr), *]) => {
+		SubCommandArg {
+			base: BaseArg {
+				name: $name,
+				description: $desc,
+				required: false,
+			},
+			args: &[$(&$args), *],
 		}
 	};
 }
@@ -53,23 +84,13 @@ macro_rules! command_inner {
 					&& other.default_member_permissions == $permissions
 					&& other.name == $name
 					&& other.description == $desc
-					&& {
-						if other.options.len() != $args.len() {
-							false
-						} else {
-							let old_args = other
-								.options
-								.iter()
-								.map(|arg| (arg.name.to_owned(), arg))
-								.collect::<HashMap<_, _>>();
-							$args.iter().all(|new_arg: &&dyn IntoCommandArg| {
-								old_args
-									.get(new_arg.name())
-									.map(|old_arg| new_arg == old_arg)
-									.unwrap_or(false)
-							})
-						}
-					}
+					&& $crate::command::args::localizations_eq(
+						&other.name_localizations,
+						&$crate::locale::name_localizations($name),
+					) && $crate::command::args::localizations_eq(
+					&other.description_localizations,
+					&$crate::locale::description_localizations($desc),
+				) && $crate::command::args::args_eq($args, &other.options)
 			}
 		}
 
@@ -89,6 +110,12 @@ macro_rules! command_inner {
 				if let Some(permissions) = $permissions {
 					cmd = cmd.default_member_permissions(permissions);
 				}
+				for (locale, name) in $crate::locale::name_localizations($name) {
+					cmd = cmd.name_localized(locale, name);
+				}
+				for (locale, description) in $crate::locale::description_localizations($desc) {
+					cmd = cmd.description_localized(locale, description);
+				}
 				cmd
 			}
 		}
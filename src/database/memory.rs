@@ -0,0 +1,754 @@
+use std::{
+	collections::HashMap,
+	sync::{Arc, Mutex},
+	time::{SystemTime, UNIX_EPOCH},
+};
+
+use serenity::{async_trait, model::Timestamp};
+
+use crate::{
+	locale::Language,
+	matcher::{Found, Matcher},
+	template::ReplyTemplate,
+};
+
+use super::{
+	kept_times, store::CounterStore, Change, CountedMessage, EmoteOrder, LeaderboardPage,
+	LeaderboardRow, MessageEvent, NewCount, Period, UserCount, VerboseLevel,
+};
+
+/// Keeps counts and user options in memory until the bot stops.
+/// Every server uses the default settings, and the audit log isn't kept
+#[derive(Default)]
+pub struct MemoryStore {
+	data: Mutex<MemoryData>,
+	matcher: Arc<Matcher>,
+}
+
+#[derive(Default)]
+struct MemoryData {
+	/// By user, server and emote
+	counts: HashMap<(u64, u64, Box<str>), u32>,
+	events: Vec<MemoryEvent>,
+	options: HashMap<u64, MemoryOptions>,
+	/// By user and server
+	server_options: HashMap<(u64, u64), MemoryServerOptions>,
+	mute_all: HashMap<u64, u32>,
+}
+
+struct MemoryEvent {
+	user_id: u64,
+	server_id: u64,
+	message_id: u64,
+	emote: Box<str>,
+	reply_id: Option<u64>,
+	/// Unix timestamp
+	created_at: u64,
+}
+
+#[derive(Default)]
+struct MemoryOptions {
+	opt_out: bool,
+	silent: Option<u32>,
+	/// Only set while the user appears on the global leaderboard
+	username: Option<String>,
+}
+
+#[derive(Default)]
+struct MemoryServerOptions {
+	opt_out: Option<bool>,
+	/// `None` while the global option is used
+	silent: Option<Option<u32>>,
+}
+
+impl MemoryData {
+	/// Muting the server wins over the user's server setting, which wins over their global one
+	fn verbose_level(&self, user_id: u64, server_id: u64) -> VerboseLevel {
+		let silent = match self.mute_all.get(&server_id) {
+			Some(mute_all) => Some(*mute_all),
+			None => self
+				.server_options
+				.get(&(user_id, server_id))
+				.and_then(|options| options.silent)
+				.unwrap_or_else(|| {
+					self.options
+						.get(&user_id)
+						.and_then(|options| options.silent)
+				}),
+		};
+		silent.map(|v| v as i32).into()
+	}
+
+	/// The server's setting if the user has one there, otherwise their global one
+	fn opted_out(&self, user_id: u64, server_id: Option<u64>) -> bool {
+		server_id
+			.and_then(|server_id| self.server_options.get(&(user_id, server_id)))
+			.and_then(|options| options.opt_out)
+			.or_else(|| self.options.get(&user_id).map(|options| options.opt_out))
+			.unwrap_or(false)
+	}
+
+	/// Counts every found emote at its `created_at`, `None` if the user opted out
+	fn count(
+		&mut self,
+		user_id: u64,
+		server_id: u64,
+		message_id: u64,
+		found: &[Found<'_>],
+		created_at: &[u64],
+	) -> Option<CountedMessage> {
+		if self.opted_out(user_id, Some(server_id)) {
+			return None;
+		}
+
+		let mut counts: Vec<NewCount> = Vec::new();
+		for (found, &created_at) in found.iter().zip(created_at) {
+			let count = self
+				.counts
+				.entry((user_id, server_id, found.emote.into()))
+				.or_default();
+			*count += 1;
+			match counts.iter_mut().find(|c| *c.emote == *found.emote) {
+				Some(new_count) => {
+					new_count.added += 1;
+					new_count.count = *count;
+				}
+				None => counts.push(NewCount {
+					emote: found.emote.into(),
+					added: 1,
+					count: *count,
+				}),
+			}
+			self.events.push(MemoryEvent {
+				user_id,
+				server_id,
+				message_id,
+				emote: found.emote.into(),
+				reply_id: None,
+				created_at,
+			});
+		}
+		Some(CountedMessage {
+			counts,
+			verbose: self.verbose_level(user_id, server_id),
+		})
+	}
+
+	/// Removes the events of the messages and rolls back their counts
+	fn remove(&mut self, message_ids: &[u64]) -> Vec<MemoryEvent> {
+		let (removed, events) = std::mem::take(&mut self.events)
+			.into_iter()
+			.partition::<Vec<_>, _>(|event| message_ids.contains(&event.message_id));
+		self.events = events;
+
+		for event in &removed {
+			let key = (event.user_id, event.server_id, event.emote.clone());
+			// counts that hit zero are removed, like in the database
+			match self.counts.get_mut(&key) {
+				Some(count) if *count > 1 => *count -= 1,
+				_ => {
+					self.counts.remove(&key);
+				}
+			}
+		}
+		removed
+	}
+
+	fn username(&self, user_id: u64) -> Option<&String> {
+		self.options
+			.get(&user_id)
+			.and_then(|options| options.username.as_ref())
+	}
+}
+
+fn now() -> u64 {
+	SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map_or(0, |since| since.as_secs())
+}
+
+/// The day of the month of a day counted from 1970-01-01, after Howard Hinnant's `civil_from_days`
+fn day_of_month(days: i64) -> i64 {
+	let z = days + 719468;
+	let era = z.div_euclid(146097);
+	let doe = z - era * 146097;
+	let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+	let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+	let mp = (5 * doy + 2) / 153;
+	doy - (153 * mp + 2) / 5 + 1
+}
+
+/// When the period started in UTC, like `date_trunc`, `None` for all time
+fn period_start(period: Period, now: u64) -> Option<u64> {
+	let days = (now / 86400) as i64;
+	let start = match period {
+		Period::All => return None,
+		Period::Day => days,
+		// 1970-01-01 was a Thursday, weeks start on Monday
+		Period::Week => days - (days + 3) % 7,
+		Period::Month => days - (day_of_month(days) - 1),
+	};
+	Some(start as u64 * 86400)
+}
+
+#[async_trait]
+impl CounterStore for MemoryStore {
+	async fn matcher(&self, _server_id: u64) -> sqlx::Result<Arc<Matcher>> {
+		Ok(self.matcher.clone())
+	}
+	async fn is_channel_counted(
+		&self,
+		_server_id: u64,
+		_channel_id: u64,
+		_parent_id: Option<u64>,
+	) -> sqlx::Result<bool> {
+		Ok(true)
+	}
+	async fn language(&self, _server_id: u64) -> sqlx::Result<Language> {
+		Ok(Language::default())
+	}
+	async fn reply_templates(&self, _server_id: u64) -> sqlx::Result<Vec<ReplyTemplate>> {
+		Ok(Vec::new())
+	}
+
+	async fn count_message(
+		&self,
+		user_id: u64,
+		server_id: u64,
+		_channel_id: u64,
+		message_id: u64,
+		found: &[Found<'_>],
+	) -> sqlx::Result<Option<CountedMessage>> {
+		let created_at = vec![now(); found.len()];
+		let mut data = self.data.lock().unwrap();
+		Ok(data.count(user_id, server_id, message_id, found, &created_at))
+	}
+	async fn recount_message(
+		&self,
+		user_id: u64,
+		server_id: u64,
+		_channel_id: u64,
+		message_id: u64,
+		sent_at: Timestamp,
+		found: &[Found<'_>],
+	) -> sqlx::Result<Option<CountedMessage>> {
+		let mut data = self.data.lock().unwrap();
+		let removed = data.remove(&[message_id]);
+		let created_at: Vec<_> = kept_times(
+			removed
+				.iter()
+				.map(|event| (&*event.emote, event.created_at)),
+			found,
+		)
+		.into_iter()
+		.map(|kept| kept.unwrap_or(sent_at.unix_timestamp() as u64))
+		.collect();
+		Ok(data.count(user_id, server_id, message_id, found, &created_at))
+	}
+	async fn set_reply(&self, message_id: u64, reply_id: u64) -> sqlx::Result<()> {
+		let mut data = self.data.lock().unwrap();
+		for event in data.events.iter_mut() {
+			if event.message_id == message_id {
+				event.reply_id = Some(reply_id);
+			}
+		}
+		Ok(())
+	}
+	async fn message_events(&self, message_id: u64) -> sqlx::Result<Vec<MessageEvent>> {
+		let data = self.data.lock().unwrap();
+		Ok(data
+			.events
+			.iter()
+			.filter(|event| event.message_id == message_id)
+			.map(|event| MessageEvent {
+				emote: event.emote.clone(),
+				reply_id: event.reply_id.map(Into::into),
+			})
+			.collect())
+	}
+	async fn remove_messages(&self, message_ids: &[u64]) -> sqlx::Result<u64> {
+		let mut data = self.data.lock().unwrap();
+		Ok(data.remove(message_ids).len() as u64)
+	}
+
+	async fn user_counts(
+		&self,
+		user_id: u64,
+		server_id: Option<u64>,
+	) -> sqlx::Result<Vec<UserCount>> {
+		let data = self.data.lock().unwrap();
+		let mut totals: HashMap<&str, u64> = HashMap::new();
+		for ((user, server, emote), count) in &data.counts {
+			if *user == user_id
+				&& server_id.is_none_or(|server_id| *server == server_id)
+				&& !data.opted_out(*user, Some(*server))
+			{
+				*totals.entry(emote).or_default() += u64::from(*count);
+			}
+		}
+		let mut counts: Vec<_> = totals
+			.into_iter()
+			.map(|(emote, count)| UserCount {
+				emote: emote.into(),
+				count: count.into(),
+			})
+			.collect();
+		counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.emote.cmp(&b.emote)));
+		Ok(counts)
+	}
+	async fn user_rank(
+		&self,
+		user_id: u64,
+		server_id: u64,
+		emote: &str,
+	) -> sqlx::Result<Option<i64>> {
+		let data = self.data.lock().unwrap();
+		let counts: Vec<(u64, u32)> = data
+			.counts
+			.iter()
+			.filter(|((user, server, e), _)| {
+				*server == server_id && **e == *emote && !data.opted_out(*user, Some(*server))
+			})
+			.map(|((user, _, _), count)| (*user, *count))
+			.collect();
+		let Some(&(_, count)) = counts.iter().find(|(user, _)| *user == user_id) else {
+			return Ok(None);
+		};
+		// dense ranks count the distinct higher counts
+		let mut higher: Vec<u32> = counts
+			.iter()
+			.map(|(_, c)| *c)
+			.filter(|c| *c > count)
+			.collect();
+		higher.sort_unstable();
+		higher.dedup();
+		Ok(Some(higher.len() as i64 + 1))
+	}
+	async fn leaderboard(
+		&self,
+		server_id: Option<u64>,
+		emote: Option<&str>,
+		order: EmoteOrder,
+		period: Period,
+		offset: u32,
+		limit: u32,
+	) -> sqlx::Result<LeaderboardPage> {
+		let data = self.data.lock().unwrap();
+		let in_server = |server: u64| server_id.is_none_or(|server_id| server == server_id);
+		let counts: Vec<(u64, &str, u32)> = match period_start(period, now()) {
+			None => data
+				.counts
+				.iter()
+				.filter(|((_, server, _), _)| in_server(*server))
+				.map(|((user, server, emote), count)| ((*user, *server), &**emote, *count))
+				.filter(|((user, server), _, _)| !data.opted_out(*user, Some(*server)))
+				.map(|((user, _), emote, count)| (user, emote, count))
+				.collect(),
+			Some(start) => data
+				.events
+				.iter()
+				.filter(|event| in_server(event.server_id) && event.created_at >= start)
+				.filter(|event| !data.opted_out(event.user_id, Some(event.server_id)))
+				.map(|event| (event.user_id, &*event.emote, 1))
+				.collect(),
+		};
+
+		// users only appear globally if they chose to
+		let mut totals: HashMap<(u64, &str), u32> = HashMap::new();
+		for (user, counted_emote, count) in counts {
+			if server_id.is_none() && data.username(user).is_none() {
+				continue;
+			}
+			let counted_emote = match emote {
+				None => "",
+				Some("*") => counted_emote,
+				Some(emote) if emote == counted_emote => counted_emote,
+				Some(_) => continue,
+			};
+			*totals.entry((user, counted_emote)).or_default() += count;
+		}
+
+		let mut by_emote: HashMap<&str, Vec<(u64, u32)>> = HashMap::new();
+		for ((user, emote), count) in totals {
+			by_emote.entry(emote).or_default().push((user, count));
+		}
+		let mut emotes: Vec<(&str, u32, Vec<(u64, u32)>)> = by_emote
+			.into_iter()
+			.map(|(emote, mut users)| {
+				users.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+				let total = users.iter().map(|(_, count)| count).sum();
+				(emote, total, users)
+			})
+			.collect();
+		emotes.sort_by(|a, b| match order {
+			EmoteOrder::Total => b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)),
+			EmoteOrder::Name => a.0.cmp(b.0),
+		});
+
+		// one extra row tells whether there is a next page
+		let (offset, end) = (u64::from(offset), u64::from(offset + limit));
+		let mut has_next = false;
+		let mut rows = Vec::new();
+		for (emote, _, users) in emotes {
+			let mut rank = 0;
+			let mut previous = None;
+			for (position, (user, count)) in users.into_iter().enumerate() {
+				let position = position as u64 + 1;
+				if previous != Some(count) {
+					rank += 1;
+					previous = Some(count);
+				}
+				if position <= offset {
+					continue;
+				}
+				if position > end {
+					has_next = true;
+					break;
+				}
+				rows.push(LeaderboardRow {
+					emote: emote.into(),
+					user_id: user.into(),
+					count: count.into(),
+					rank: (rank as u64).into(),
+					position: position.into(),
+					username: server_id
+						.is_none()
+						.then(|| data.username(user).cloned())
+						.flatten(),
+				});
+			}
+		}
+		Ok(LeaderboardPage { rows, has_next })
+	}
+
+	async fn set_opt_out(
+		&self,
+		user_id: u64,
+		server_id: Option<u64>,
+		value: bool,
+	) -> sqlx::Result<Option<bool>> {
+		let mut data = self.data.lock().unwrap();
+		Ok(match server_id {
+			Some(server_id) => data
+				.server_options
+				.entry((user_id, server_id))
+				.or_default()
+				.opt_out
+				.replace(value),
+			None => {
+				let old = data.options.get(&user_id).map(|options| options.opt_out);
+				data.options.entry(user_id).or_default().opt_out = value;
+				old
+			}
+		})
+	}
+	async fn is_opt_out(&self, user_id: u64, server_id: Option<u64>) -> sqlx::Result<bool> {
+		Ok(self.data.lock().unwrap().opted_out(user_id, server_id))
+	}
+	async fn set_silent(
+		&self,
+		user_id: u64,
+		server_id: Option<u64>,
+		value: Option<u32>,
+	) -> sqlx::Result<Option<Option<u32>>> {
+		let mut data = self.data.lock().unwrap();
+		Ok(match server_id {
+			Some(server_id) => data
+				.server_options
+				.entry((user_id, server_id))
+				.or_default()
+				.silent
+				.replace(value),
+			None => {
+				let options = data.options.entry(user_id).or_default();
+				Some(std::mem::replace(&mut options.silent, value))
+			}
+		})
+	}
+	async fn set_global(&self, user_id: u64, username: Option<&str>) -> sqlx::Result<()> {
+		let mut data = self.data.lock().unwrap();
+		let options = data.options.entry(user_id).or_default();
+		options.username = username.map(ToOwned::to_owned);
+		Ok(())
+	}
+	async fn mute_all(&self, server_id: u64, value: Option<u32>) -> sqlx::Result<Option<u32>> {
+		let mut data = self.data.lock().unwrap();
+		Ok(match value {
+			Some(value) => data.mute_all.insert(server_id, value),
+			None => data.mute_all.remove(&server_id),
+		})
+	}
+	async fn audit(
+		&self,
+		_actor_id: u64,
+		_server_id: u64,
+		_change: Change<'_>,
+	) -> sqlx::Result<()> {
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const SERVER: u64 = 10;
+
+	async fn count(
+		store: &MemoryStore,
+		user_id: u64,
+		message_id: u64,
+		emotes: &[&str],
+	) -> Option<CountedMessage> {
+		let found: Vec<_> = emotes
+			.iter()
+			.map(|emote| Found {
+				emote,
+				variant: emote,
+				text: emote,
+			})
+			.collect();
+		store
+			.count_message(user_id, SERVER, 1, message_id, &found)
+			.await
+			.unwrap()
+	}
+
+	/// Emote, user, count and rank of every row
+	fn rows(page: &LeaderboardPage) -> Vec<(&str, u64, u32, u64)> {
+		page.rows
+			.iter()
+			.map(|row| (&*row.emote, row.user_id.0, row.count.0, row.rank.0))
+			.collect()
+	}
+
+	async fn leaderboard(
+		store: &MemoryStore,
+		emote: Option<&str>,
+		period: Period,
+	) -> LeaderboardPage {
+		store
+			.leaderboard(Some(SERVER), emote, EmoteOrder::Name, period, 0, 10)
+			.await
+			.unwrap()
+	}
+
+	#[tokio::test]
+	async fn counts_every_found_emote() {
+		let store = MemoryStore::default();
+		let counted = count(&store, 1, 100, &["x3", "x3", ":3"]).await.unwrap();
+		let counts: Vec<_> = counted
+			.counts
+			.iter()
+			.map(|c| (&*c.emote, c.added, c.count))
+			.collect();
+		assert_eq!(counts, [("x3", 2, 2), (":3", 1, 1)]);
+		assert!(matches!(counted.verbose, VerboseLevel::Verbose));
+
+		let counted = count(&store, 1, 101, &["x3"]).await.unwrap();
+		assert_eq!(counted.counts[0].count, 3);
+
+		let counts = store.user_counts(1, Some(SERVER)).await.unwrap();
+		let counts: Vec<_> = counts.iter().map(|c| (&*c.emote, c.count.0)).collect();
+		assert_eq!(counts, [("x3", 3), (":3", 1)]);
+		assert_eq!(store.message_events(100).await.unwrap().len(), 3);
+	}
+
+	#[tokio::test]
+	async fn removing_messages_rolls_back_their_counts() {
+		let store = MemoryStore::default();
+		count(&store, 1, 100, &["x3", "owo"]).await;
+		count(&store, 1, 101, &["x3"]).await;
+		store.set_reply(100, 200).await.unwrap();
+		let events = store.message_events(100).await.unwrap();
+		assert!(events
+			.iter()
+			.all(|event| event.reply_id.map(|id| id.0) == Some(200)));
+
+		assert_eq!(store.remove_messages(&[100, 999]).await.unwrap(), 2);
+		assert!(store.message_events(100).await.unwrap().is_empty());
+		let counts = store.user_counts(1, None).await.unwrap();
+		let counts: Vec<_> = counts.iter().map(|c| (&*c.emote, c.count.0)).collect();
+		// counts that hit zero are gone
+		assert_eq!(counts, [("x3", 1)]);
+	}
+
+	#[tokio::test]
+	async fn recounting_an_edit_keeps_when_emotes_were_counted() {
+		let store = MemoryStore::default();
+		count(&store, 1, 100, &["x3", "owo"]).await;
+		store.data.lock().unwrap().events[0].created_at = 0;
+
+		let found = [Found {
+			emote: "x3",
+			variant: "x3",
+			text: "x3",
+		}; 2];
+		let sent_at = Timestamp::from_unix_timestamp(86400).unwrap();
+		let counted = store
+			.recount_message(1, SERVER, 1, 100, sent_at, &found)
+			.await
+			.unwrap()
+			.unwrap();
+		assert_eq!((counted.counts[0].added, counted.counts[0].count), (2, 2));
+
+		let data = store.data.lock().unwrap();
+		let times: Vec<_> = data.events.iter().map(|event| event.created_at).collect();
+		// the emote counted before keeps its time, the one the edit added is from when it was sent
+		assert_eq!(times, [0, 86400]);
+		assert!(!data.counts.contains_key(&(1, SERVER, "owo".into())));
+	}
+
+	#[tokio::test]
+	async fn opted_out_users_are_not_counted() {
+		let store = MemoryStore::default();
+		count(&store, 1, 100, &["x3"]).await;
+		assert_eq!(store.set_opt_out(1, None, true).await.unwrap(), None);
+		assert!(store.is_opt_out(1, Some(SERVER)).await.unwrap());
+		assert!(count(&store, 1, 101, &["x3"]).await.is_none());
+		assert!(store.user_counts(1, None).await.unwrap().is_empty());
+		assert!(leaderboard(&store, Some("x3"), Period::All)
+			.await
+			.rows
+			.is_empty());
+
+		// the server's setting wins over the global one
+		assert_eq!(
+			store.set_opt_out(1, Some(SERVER), false).await.unwrap(),
+			None
+		);
+		assert!(!store.is_opt_out(1, Some(SERVER)).await.unwrap());
+		assert!(store.is_opt_out(1, None).await.unwrap());
+		assert_eq!(
+			count(&store, 1, 102, &["x3"]).await.unwrap().counts[0].count,
+			2
+		);
+	}
+
+	#[tokio::test]
+	async fn leaderboard_ranks_by_count() {
+		let store = MemoryStore::default();
+		for (user_id, times) in [(1, 1), (2, 3), (3, 5), (4, 3)] {
+			for message_id in 0..times {
+				count(&store, user_id, user_id * 100 + message_id, &["x3"]).await;
+			}
+		}
+		count(&store, 1, 900, &["owo", "owo"]).await;
+
+		let page = leaderboard(&store, Some("x3"), Period::All).await;
+		// ties share a rank and are ordered by user
+		assert_eq!(
+			rows(&page),
+			[
+				("x3", 3, 5, 1),
+				("x3", 2, 3, 2),
+				("x3", 4, 3, 2),
+				("x3", 1, 1, 3)
+			]
+		);
+		assert!(!page.has_next);
+
+		let page = store
+			.leaderboard(
+				Some(SERVER),
+				Some("x3"),
+				EmoteOrder::Name,
+				Period::All,
+				1,
+				2,
+			)
+			.await
+			.unwrap();
+		assert_eq!(rows(&page), [("x3", 2, 3, 2), ("x3", 4, 3, 2)]);
+		assert!(page.has_next);
+
+		let page = leaderboard(&store, None, Period::All).await;
+		assert_eq!(rows(&page)[0], ("", 3, 5, 1));
+		// 1 has 3 with their owos
+		assert_eq!(rows(&page)[1], ("", 1, 3, 2));
+
+		let page = leaderboard(&store, Some("*"), Period::All).await;
+		let emotes: Vec<_> = page.rows.iter().map(|row| &*row.emote).collect();
+		assert_eq!(emotes, ["owo", "x3", "x3", "x3", "x3"]);
+		let page = store
+			.leaderboard(
+				Some(SERVER),
+				Some("*"),
+				EmoteOrder::Total,
+				Period::All,
+				0,
+				1,
+			)
+			.await
+			.unwrap();
+		assert_eq!(rows(&page), [("x3", 3, 5, 1), ("owo", 1, 2, 1)]);
+	}
+
+	#[tokio::test]
+	async fn global_leaderboard_only_has_users_who_opted_in() {
+		let store = MemoryStore::default();
+		count(&store, 1, 100, &["x3"]).await;
+		count(&store, 2, 200, &["x3"]).await;
+		store.set_global(2, Some("two")).await.unwrap();
+
+		let page = store
+			.leaderboard(None, Some("x3"), EmoteOrder::Name, Period::All, 0, 10)
+			.await
+			.unwrap();
+		assert_eq!(rows(&page), [("x3", 2, 1, 1)]);
+		assert_eq!(page.rows[0].username.as_deref(), Some("two"));
+	}
+
+	#[tokio::test]
+	async fn periods_only_rank_recent_counts() {
+		let store = MemoryStore::default();
+		count(&store, 1, 100, &["x3"]).await;
+		{
+			let mut data = store.data.lock().unwrap();
+			*data.counts.entry((2, SERVER, "x3".into())).or_default() += 2;
+			for message_id in [200, 201] {
+				data.events.push(MemoryEvent {
+					user_id: 2,
+					server_id: SERVER,
+					message_id,
+					emote: "x3".into(),
+					reply_id: None,
+					created_at: 0,
+				});
+			}
+		}
+
+		let page = leaderboard(&store, Some("x3"), Period::All).await;
+		assert_eq!(rows(&page), [("x3", 2, 2, 1), ("x3", 1, 1, 2)]);
+		for period in [Period::Day, Period::Week, Period::Month] {
+			let page = leaderboard(&store, Some("x3"), period).await;
+			assert_eq!(rows(&page), [("x3", 1, 1, 1)]);
+		}
+	}
+
+	#[test]
+	fn periods_start_like_date_trunc() {
+		// Thursday, 2024-03-14 12:00 UTC
+		let now = 1710417600;
+		assert_eq!(period_start(Period::All, now), None);
+		assert_eq!(period_start(Period::Day, now), Some(1710374400));
+		assert_eq!(period_start(Period::Week, now), Some(1710115200));
+		assert_eq!(period_start(Period::Month, now), Some(1709251200));
+	}
+
+	#[tokio::test]
+	async fn muting_the_server_wins_over_user_settings() {
+		let store = MemoryStore::default();
+		store.set_silent(1, None, Some(0)).await.unwrap();
+		let counted = count(&store, 1, 100, &["x3"]).await.unwrap();
+		assert!(matches!(counted.verbose, VerboseLevel::Silent));
+
+		store.set_silent(1, Some(SERVER), None).await.unwrap();
+		let counted = count(&store, 1, 101, &["x3"]).await.unwrap();
+		assert!(matches!(counted.verbose, VerboseLevel::Verbose));
+
+		store.mute_all(SERVER, Some(3)).await.unwrap();
+		let counted = count(&store, 1, 102, &["x3"]).await.unwrap();
+		assert!(matches!(counted.verbose, VerboseLevel::Every(n) if n.get() == 3));
+	}
+}
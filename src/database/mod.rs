@@ -1,26 +1,444 @@
+mod memory;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod store;
 mod unsigned;
 
-use std::{fmt::Display, num::NonZeroU32};
+use std::{
+	collections::HashMap,
+	fmt::Display,
+	num::NonZeroU32,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc, RwLock,
+	},
+};
 
-use sqlx::PgPool;
+use serde::Serialize;
+use serenity::model::Timestamp;
+#[cfg(feature = "sqlite")]
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use sqlx::{
+	migrate::{Migrate, MigrateError, Migrator},
+	PgConnection, PgExecutor, PgPool,
+};
 use unsigned::{PsqlU32, PsqlU64};
 
+pub use memory::MemoryStore;
+pub use store::CounterStore;
+
+use crate::{
+	locale::Language,
+	matcher::{EmoteAlias, Found, MatchMode, Matcher, ServerPattern},
+	template::ReplyTemplate,
+};
+
 pub struct UserCount {
 	pub emote: Box<str>,
 	pub count: PsqlU64,
 }
 
+pub struct NewCount {
+	pub emote: Box<str>,
+	pub added: u32,
+	pub count: u32,
+}
+
+/// The new counts of a counted message, and how the user wants to hear about them
+pub struct CountedMessage {
+	pub counts: Vec<NewCount>,
+	pub verbose: VerboseLevel,
+}
+
+pub struct MessageEvent {
+	pub emote: Box<str>,
+	pub reply_id: Option<PsqlU64>,
+}
+
 #[derive(Debug)]
 pub struct LeaderboardRow {
 	pub emote: Box<str>,
 	pub user_id: PsqlU64,
 	pub count: PsqlU32,
 	pub rank: PsqlU64,
+	/// 1-based position in the emote's ranking, unique even when ranks are tied
+	pub position: PsqlU64,
+	/// Only known for global leaderboards, where mentions don't resolve
+	pub username: Option<String>,
+}
+
+fn escape_markdown(text: &str) -> String {
+	text.chars().fold(String::new(), |mut escaped, c| {
+		if "\\*_~`|>#-[]()".contains(c) {
+			escaped.push('\\');
+		}
+		escaped.push(c);
+		escaped
+	})
+}
+
+pub struct LeaderboardPage {
+	pub rows: Vec<LeaderboardRow>,
+	pub has_next: bool,
 }
 impl Display for LeaderboardRow {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		write!(f, "{}\\. <@{}> - {}", self.rank, self.user_id, self.count)
+		match &self.username {
+			Some(username) => write!(
+				f,
+				"{}\\. {} - {}",
+				self.rank,
+				escape_markdown(username),
+				self.count
+			),
+			None => write!(f, "{}\\. <@{}> - {}", self.rank, self.user_id, self.count),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+	Day,
+	Week,
+	Month,
+	All,
+}
+impl Period {
+	pub fn from_str(value: &str) -> Option<Self> {
+		match value {
+			"day" => Some(Self::Day),
+			"week" => Some(Self::Week),
+			"month" => Some(Self::Month),
+			"all" => Some(Self::All),
+			_ => None,
+		}
 	}
+
+	pub fn as_str(self) -> &'static str {
+		match self {
+			Self::Day => "day",
+			Self::Week => "week",
+			Self::Month => "month",
+			Self::All => "all",
+		}
+	}
+
+	pub fn from_choice(choice: &str) -> Option<Self> {
+		match choice {
+			"today" => Some(Self::Day),
+			"this week" => Some(Self::Week),
+			"this month" => Some(Self::Month),
+			"all time" => Some(Self::All),
+			_ => None,
+		}
+	}
+
+	/// The `date_trunc` field the period starts at, `None` for all time
+	fn trunc_field(self) -> Option<&'static str> {
+		match self {
+			Self::All => None,
+			other => Some(other.as_str()),
+		}
+	}
+}
+/// The order of the emotes in a leaderboard with every emote
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmoteOrder {
+	#[default]
+	Name,
+	Total,
+}
+impl EmoteOrder {
+	pub fn from_str(value: &str) -> Option<Self> {
+		match value {
+			"name" => Some(Self::Name),
+			"total" => Some(Self::Total),
+			_ => None,
+		}
+	}
+
+	pub fn as_str(self) -> &'static str {
+		match self {
+			Self::Name => "name",
+			Self::Total => "total",
+		}
+	}
+
+	pub fn from_choice(choice: &str) -> Option<Self> {
+		match choice {
+			"alphabetical" => Some(Self::Name),
+			"most counted" => Some(Self::Total),
+			_ => None,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelMode {
+	Allow,
+	Block,
+}
+impl ChannelMode {
+	pub fn as_str(self) -> &'static str {
+		match self {
+			Self::Allow => "allow",
+			Self::Block => "block",
+		}
+	}
+}
+impl From<String> for ChannelMode {
+	fn from(value: String) -> Self {
+		match value.as_str() {
+			"allow" => Self::Allow,
+			_ => Self::Block,
+		}
+	}
+}
+
+pub struct ChannelOption {
+	pub channel_id: PsqlU64,
+	pub mode: ChannelMode,
+}
+
+/// Everything stored about a user, for them to export
+#[derive(Serialize)]
+pub struct UserData {
+	pub user_id: PsqlU64,
+	pub options: Option<UserOptions>,
+	pub server_options: Vec<UserServerOptions>,
+	pub counts: Vec<ServerCount>,
+	pub events: Vec<CountEvent>,
+	/// Changes the user made or that were made to their counts and settings
+	pub audit: Vec<AuditEntry>,
+}
+
+#[derive(Serialize)]
+pub struct UserOptions {
+	pub opt_out: bool,
+	pub silent: Option<i32>,
+	pub global: bool,
+	pub username: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct UserServerOptions {
+	pub server_id: PsqlU64,
+	pub opt_out: Option<bool>,
+	pub silent: Option<i32>,
+	pub silent_set: bool,
+}
+
+#[derive(Serialize)]
+pub struct ServerCount {
+	pub server_id: PsqlU64,
+	pub emote: String,
+	pub count: i32,
+}
+
+#[derive(Serialize)]
+pub struct CountEvent {
+	pub server_id: PsqlU64,
+	pub channel_id: PsqlU64,
+	pub message_id: PsqlU64,
+	pub reply_id: Option<PsqlU64>,
+	pub emote: String,
+	pub variant: String,
+	pub matched: String,
+	/// RFC 3339 in UTC
+	pub created_at: String,
+}
+
+/// A change made through a command in a server, for the audit log
+pub struct Change<'a> {
+	pub action: &'a str,
+	/// The user whose counts or settings were changed
+	pub user_id: Option<PsqlU64>,
+	/// What was changed, as shown in `/audit`
+	pub target: Option<String>,
+	pub old_value: Option<String>,
+	pub new_value: Option<String>,
+	pub reason: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+pub struct AuditEntry {
+	pub server_id: PsqlU64,
+	/// `None` once the actor's data was deleted
+	pub actor_id: Option<PsqlU64>,
+	/// `None` once the user's data was deleted
+	pub user_id: Option<PsqlU64>,
+	pub action: String,
+	pub target: Option<String>,
+	pub old_value: Option<String>,
+	pub new_value: Option<String>,
+	pub reason: Option<String>,
+	/// Unix timestamp
+	pub created_at: i64,
+}
+
+/// Checks the options, counts and records the events in a single statement,
+/// at their `created_at` or now
+async fn insert_counts(
+	executor: impl PgExecutor<'_>,
+	user_id: i64,
+	server_id: i64,
+	channel_id: i64,
+	message_id: i64,
+	found: &[Found<'_>],
+	created_at: &[Option<String>],
+) -> sqlx::Result<Option<CountedMessage>> {
+	let emotes: Vec<&str> = found.iter().map(|found| found.emote).collect();
+	let variants: Vec<&str> = found.iter().map(|found| found.variant).collect();
+	let matched: Vec<&str> = found.iter().map(|found| found.text).collect();
+	let rows = sqlx::query!(
+		r#"WITH settings AS (
+			SELECT
+				opted_out($1, $2) AS opt_out,
+				COALESCE(
+					server_options.mute_all,
+					CASE WHEN user_server_options.silent_set
+					THEN user_server_options.silent
+					ELSE options.silent END
+				) AS silent
+			FROM (SELECT) AS _
+			LEFT JOIN server_options ON server_options.server_id = $2
+			LEFT JOIN options ON options.user_id = $1
+			LEFT JOIN user_server_options
+			ON user_server_options.server_id = $2 AND user_server_options.user_id = $1
+		), found AS (
+			SELECT * FROM unnest($5::text[], $6::text[], $7::text[], $8::text[])
+			WITH ORDINALITY AS found (emote, variant, matched, created_at, position)
+			WHERE NOT (SELECT opt_out FROM settings)
+		), events AS (
+			INSERT INTO count_events (user_id, server_id, channel_id, message_id, emote, variant, matched, created_at)
+			SELECT $1, $2, $3, $4, emote, variant, matched,
+				COALESCE(created_at::timestamptz, now())
+			FROM found ORDER BY position
+		), added AS (
+			SELECT emote, COUNT(*)::integer AS added, MIN(position) AS position
+			FROM found
+			GROUP BY emote
+		), counts AS (
+			INSERT INTO counter (user_id, server_id, emote, count)
+			SELECT $1, $2, emote, added FROM added
+			ON CONFLICT (user_id, server_id, emote) DO
+			UPDATE SET count = counter.count + EXCLUDED.count
+			RETURNING emote, count
+		)
+		SELECT
+			settings.opt_out AS "opt_out!",
+			settings.silent,
+			counts.emote AS "emote?",
+			added.added AS "added?",
+			counts.count AS "count?"
+		FROM settings
+		LEFT JOIN counts ON true
+		LEFT JOIN added ON added.emote = counts.emote
+		ORDER BY added.position"#,
+		user_id,
+		server_id,
+		channel_id,
+		message_id,
+		&emotes as &[&str],
+		&variants as &[&str],
+		&matched as &[&str],
+		created_at as &[Option<String>],
+	)
+	.fetch_all(executor)
+	.await?;
+
+	// there is always a row for the settings
+	let Some(settings) = rows.first() else {
+		return Ok(None);
+	};
+	if settings.opt_out {
+		return Ok(None);
+	}
+	let verbose = settings.silent.into();
+	let counts = rows
+		.into_iter()
+		.filter_map(|row| {
+			Some(NewCount {
+				emote: row.emote?.into(),
+				added: row.added? as u32,
+				count: row.count? as u32,
+			})
+		})
+		.collect();
+	Ok(Some(CountedMessage { counts, verbose }))
+}
+
+/// Takes back `count` of the user's emote, removing the row when it would hit zero
+/// to respect counter_count_check
+async fn roll_back_count(
+	executor: &mut PgConnection,
+	user_id: i64,
+	server_id: i64,
+	emote: &str,
+	count: i32,
+) -> sqlx::Result<()> {
+	sqlx::query!(
+		r#"DELETE FROM counter
+		WHERE user_id = $1 AND server_id = $2 AND emote = $3 AND count <= $4"#,
+		user_id,
+		server_id,
+		emote,
+		count,
+	)
+	.execute(&mut *executor)
+	.await?;
+	sqlx::query!(
+		r#"UPDATE counter SET count = count - $4
+		WHERE user_id = $1 AND server_id = $2 AND emote = $3"#,
+		user_id,
+		server_id,
+		emote,
+		count,
+	)
+	.execute(executor)
+	.await
+	.map(|_| ())
+}
+
+/// When each found emote was first counted, if the edited message counted it before.
+/// `removed` are the emotes the message counted and when, in the order they were found
+fn kept_times<'a, T>(
+	removed: impl IntoIterator<Item = (&'a str, T)>,
+	found: &[Found<'_>],
+) -> Vec<Option<T>> {
+	let mut removed: Vec<_> = removed.into_iter().collect();
+	found
+		.iter()
+		.map(|found| {
+			let kept = removed
+				.iter()
+				.position(|(emote, _)| *emote == found.emote)?;
+			Some(removed.remove(kept).1)
+		})
+		.collect()
+}
+
+async fn insert_audit(
+	executor: impl PgExecutor<'_>,
+	actor_id: Option<i64>,
+	server_id: i64,
+	change: Change<'_>,
+) -> sqlx::Result<()> {
+	sqlx::query!(
+		r#"INSERT INTO audit_log (server_id, actor_id, user_id, action, target, old_value, new_value, reason)
+		VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"#,
+		server_id,
+		actor_id,
+		change.user_id.map(i64::from),
+		change.action,
+		change.target,
+		change.old_value,
+		change.new_value,
+		change.reason,
+	)
+	.execute(executor)
+	.await
+	.map(|_| ())
 }
 
 pub enum VerboseLevel {
@@ -38,57 +456,779 @@ impl From<Option<i32>> for VerboseLevel {
 	}
 }
 
+/// 0001 is the schema of the old `sql/init` dump. Dumps from before /pattern don't have
+/// server_patterns, its IF NOT EXISTS adds it to those
+static MIGRATOR: Migrator = sqlx::migrate!();
+#[cfg(feature = "sqlite")]
+static SQLITE_MIGRATOR: Migrator = sqlx::migrate!("./migrations/sqlite");
+
+/// Fails if the database has migrations applied that this binary doesn't know about
+async fn check_migrations(
+	conn: &mut (impl Migrate + ?Sized),
+	migrator: &Migrator,
+) -> Result<(), MigrateError> {
+	conn.ensure_migrations_table().await?;
+	let known = migrator.iter().map(|m| m.version).max().unwrap_or(0);
+	match conn
+		.list_applied_migrations()
+		.await?
+		.into_iter()
+		.find(|m| m.version > known)
+	{
+		Some(unknown) => Err(MigrateError::VersionMissing(unknown.version)),
+		None => Ok(()),
+	}
+}
+
+enum Pool {
+	Postgres(PgPool),
+	#[cfg(feature = "sqlite")]
+	Sqlite(SqlitePool),
+}
+
+/// The Postgres pool, or returns what the function of the same name in `sqlite` does instead
+#[cfg(feature = "sqlite")]
+macro_rules! postgres {
+	($self:ident, $function:ident($($arg:expr),* $(,)?)) => {
+		match &$self.pool {
+			Pool::Postgres(pool) => pool,
+			Pool::Sqlite(pool) => return sqlite::$function(pool, $($arg),*).await,
+		}
+	};
+}
+#[cfg(not(feature = "sqlite"))]
+macro_rules! postgres {
+	($self:ident, $($sqlite:tt)*) => {{
+		let Pool::Postgres(pool) = &$self.pool;
+		pool
+	}};
+}
+
+/// Past this many the cached opt-outs start over, so the cache doesn't keep every user ever counted
+const MAX_CACHED_OPT_OUTS: usize = 100_000;
+/// Past this many the cached matchers and server settings start over
+const MAX_CACHED_SERVERS: usize = 10_000;
+
+/// The server settings counting a message needs, until a setter changes them
+struct ServerSettings {
+	language: Language,
+	reply_templates: Vec<ReplyTemplate>,
+	channels: Vec<ChannelOption>,
+}
+impl ServerSettings {
+	/// Blocked channels are never counted, and once a channel is allowed only allowed ones are.
+	/// Threads without a setting of their own use the one of their parent channel
+	fn is_channel_counted(&self, channel_id: u64, parent_id: Option<u64>) -> bool {
+		let mode = |id: u64| {
+			self.channels
+				.iter()
+				.find(|channel| channel.channel_id.0 == id)
+				.map(|channel| channel.mode)
+		};
+		match mode(channel_id).or_else(|| parent_id.and_then(mode)) {
+			Some(mode) => mode == ChannelMode::Allow,
+			None => !self
+				.channels
+				.iter()
+				.any(|channel| channel.mode == ChannelMode::Allow),
+		}
+	}
+}
+
+/// How often the caches were hit, and how much they hold
+pub struct CacheStats {
+	pub options_hits: u64,
+	pub options_misses: u64,
+	pub cached_options: usize,
+	pub matcher_hits: u64,
+	pub matcher_misses: u64,
+	pub cached_matchers: usize,
+	pub settings_hits: u64,
+	pub settings_misses: u64,
+	pub cached_servers: usize,
+}
+
 pub struct DatabaseHandler {
-	pool: PgPool,
+	pool: Pool,
+	matchers: RwLock<HashMap<u64, Arc<Matcher>>>,
+	/// Bumped on every change, matchers built across one aren't cached
+	matchers_generation: AtomicU64,
+	matcher_hits: AtomicU64,
+	matcher_misses: AtomicU64,
+	/// Whether the user opted out in the server, until a setter changes it
+	opt_outs: RwLock<HashMap<(u64, u64), bool>>,
+	/// Bumped on every change, options loaded across one aren't cached
+	options_generation: AtomicU64,
+	options_hits: AtomicU64,
+	options_misses: AtomicU64,
+	settings: RwLock<HashMap<u64, Arc<ServerSettings>>>,
+	/// Bumped on every change, settings loaded across one aren't cached
+	settings_generation: AtomicU64,
+	settings_hits: AtomicU64,
+	settings_misses: AtomicU64,
 }
 impl DatabaseHandler {
-	pub fn new(pool: PgPool) -> Self {
-		Self { pool }
+	/// Connects to Postgres, or to a SQLite file for a `sqlite:` url
+	pub async fn connect(url: &str) -> sqlx::Result<Self> {
+		let pool = if url.starts_with("sqlite:") {
+			Self::connect_sqlite(url).await?
+		} else {
+			Pool::Postgres(PgPool::connect(url).await?)
+		};
+		Ok(Self {
+			pool,
+			matchers: Default::default(),
+			matchers_generation: Default::default(),
+			matcher_hits: Default::default(),
+			matcher_misses: Default::default(),
+			opt_outs: Default::default(),
+			options_generation: Default::default(),
+			options_hits: Default::default(),
+			options_misses: Default::default(),
+			settings: Default::default(),
+			settings_generation: Default::default(),
+			settings_hits: Default::default(),
+			settings_misses: Default::default(),
+		})
+	}
+	#[cfg(feature = "sqlite")]
+	async fn connect_sqlite(url: &str) -> sqlx::Result<Pool> {
+		let options = url.parse::<SqliteConnectOptions>()?.create_if_missing(true);
+		// setters read the old value before writing it, one connection keeps them from racing
+		let pool = SqlitePoolOptions::new()
+			.max_connections(1)
+			.connect_with(options)
+			.await?;
+		Ok(Pool::Sqlite(pool))
+	}
+	#[cfg(not(feature = "sqlite"))]
+	async fn connect_sqlite(_url: &str) -> sqlx::Result<Pool> {
+		Err(sqlx::Error::Configuration(
+			"SQLite databases need the bot built with the sqlite feature".into(),
+		))
+	}
+
+	/// Fails if the database has migrations applied that this binary doesn't know about
+	pub async fn check_migrations(&self) -> Result<(), MigrateError> {
+		match &self.pool {
+			Pool::Postgres(pool) => check_migrations(&mut *pool.acquire().await?, &MIGRATOR).await,
+			#[cfg(feature = "sqlite")]
+			Pool::Sqlite(pool) => check_migrations(&mut *pool.acquire().await?, &SQLITE_MIGRATOR).await,
+		}
+	}
+	pub async fn migrate(&self) -> Result<(), MigrateError> {
+		self.check_migrations().await?;
+		match &self.pool {
+			Pool::Postgres(pool) => MIGRATOR.run(pool).await,
+			#[cfg(feature = "sqlite")]
+			Pool::Sqlite(pool) => SQLITE_MIGRATOR.run(pool).await,
+		}
 	}
 }
 
 impl DatabaseHandler {
-	pub async fn add_one(
+	/// Counts every found emote and records the events, `None` if the user opted out
+	pub async fn count_message(
 		&self,
 		user_id: impl Into<PsqlU64>,
 		server_id: impl Into<PsqlU64>,
-		emote: &str,
-	) -> sqlx::Result<Option<u32>> {
+		channel_id: impl Into<PsqlU64>,
+		message_id: impl Into<PsqlU64>,
+		found: &[Found<'_>],
+	) -> sqlx::Result<Option<CountedMessage>> {
 		let user_id = user_id.into();
 		let server_id = server_id.into();
-		if self.is_opt_out(user_id).await? {
+		let key = (user_id.0, server_id.0);
+		if self.cached_opt_out(key) == Some(true) {
 			return Ok(None);
 		}
-		Ok(Some(
-			query_scalar!(
-				r#"INSERT INTO counter (user_id, server_id, emote, count) VALUES ($1, $2, $3, 1)
+
+		let generation = self.options_generation.load(Ordering::Acquire);
+		let counted = self
+			.write_counts(
+				user_id,
+				server_id,
+				channel_id.into(),
+				message_id.into(),
+				found,
+			)
+			.await?;
+		self.cache_opt_out(key, generation, counted.is_none());
+		Ok(counted)
+	}
+	/// Checks the options, counts and records the events in a single statement
+	async fn write_counts(
+		&self,
+		user_id: PsqlU64,
+		server_id: PsqlU64,
+		channel_id: PsqlU64,
+		message_id: PsqlU64,
+		found: &[Found<'_>],
+	) -> sqlx::Result<Option<CountedMessage>> {
+		let pool = postgres!(
+			self,
+			count_message(
+				user_id.into(),
+				server_id.into(),
+				channel_id.into(),
+				message_id.into(),
+				found,
+			)
+		);
+		let created_at = vec![None; found.len()];
+		insert_counts(
+			pool,
+			user_id.into(),
+			server_id.into(),
+			channel_id.into(),
+			message_id.into(),
+			found,
+			&created_at,
+		)
+		.await
+	}
+	/// Replaces the counts of an edited message with the found emotes in a single transaction,
+	/// `None` if the user opted out. Emotes it counted before keep the time they were counted at,
+	/// new ones count from when the message was sent
+	pub async fn recount_message(
+		&self,
+		user_id: impl Into<PsqlU64>,
+		server_id: impl Into<PsqlU64>,
+		channel_id: impl Into<PsqlU64>,
+		message_id: impl Into<PsqlU64>,
+		sent_at: Timestamp,
+		found: &[Found<'_>],
+	) -> sqlx::Result<Option<CountedMessage>> {
+		let user_id = user_id.into();
+		let server_id = server_id.into();
+		let generation = self.options_generation.load(Ordering::Acquire);
+		let counted = self
+			.rewrite_counts(
+				user_id,
+				server_id,
+				channel_id.into(),
+				message_id.into(),
+				sent_at,
+				found,
+			)
+			.await?;
+		self.cache_opt_out((user_id.0, server_id.0), generation, counted.is_none());
+		Ok(counted)
+	}
+	async fn rewrite_counts(
+		&self,
+		user_id: PsqlU64,
+		server_id: PsqlU64,
+		channel_id: PsqlU64,
+		message_id: PsqlU64,
+		sent_at: Timestamp,
+		found: &[Found<'_>],
+	) -> sqlx::Result<Option<CountedMessage>> {
+		let pool = postgres!(
+			self,
+			recount_message(
+				user_id.into(),
+				server_id.into(),
+				channel_id.into(),
+				message_id.into(),
+				sent_at.unix_timestamp(),
+				found,
+			)
+		);
+		let mut tx = pool.begin().await?;
+		// as text, which converts back to the same timestamp
+		let removed = sqlx::query!(
+			r#"WITH deleted AS (
+				DELETE FROM count_events WHERE message_id = $1
+				RETURNING id, user_id, server_id, emote, created_at
+			)
+			SELECT user_id, server_id, emote, created_at::text AS "created_at!"
+			FROM deleted
+			ORDER BY id"#,
+			i64::from(message_id),
+		)
+		.fetch_all(&mut *tx)
+		.await?;
+
+		let mut removed_counts: HashMap<(i64, i64, &str), i32> = HashMap::new();
+		for row in &removed {
+			*removed_counts
+				.entry((row.user_id, row.server_id, &row.emote))
+				.or_default() += 1;
+		}
+		for ((user_id, server_id, emote), count) in removed_counts {
+			roll_back_count(&mut tx, user_id, server_id, emote, count).await?;
+		}
+
+		let created_at: Vec<_> = kept_times(
+			removed
+				.iter()
+				.map(|row| (row.emote.as_str(), row.created_at.clone())),
+			found,
+		)
+		.into_iter()
+		.map(|kept| Some(kept.unwrap_or_else(|| sent_at.to_string())))
+		.collect();
+		let counted = insert_counts(
+			&mut *tx,
+			user_id.into(),
+			server_id.into(),
+			channel_id.into(),
+			message_id.into(),
+			found,
+			&created_at,
+		)
+		.await?;
+		tx.commit().await?;
+		Ok(counted)
+	}
+	pub async fn message_events(
+		&self,
+		message_id: impl Into<PsqlU64>,
+	) -> sqlx::Result<Vec<MessageEvent>> {
+		let message_id = message_id.into();
+		let pool = postgres!(self, message_events(message_id.into()));
+		Ok(sqlx::query!(
+			r#"SELECT emote, reply_id FROM count_events WHERE message_id = $1 ORDER BY id"#,
+			i64::from(message_id),
+		)
+		.fetch_all(pool)
+		.await?
+		.into_iter()
+		.map(|row| MessageEvent {
+			emote: row.emote.into(),
+			reply_id: row.reply_id.map(Into::into),
+		})
+		.collect())
+	}
+	pub async fn set_reply(
+		&self,
+		message_id: impl Into<PsqlU64>,
+		reply_id: impl Into<PsqlU64>,
+	) -> sqlx::Result<()> {
+		let message_id = message_id.into();
+		let reply_id = reply_id.into();
+		let pool = postgres!(self, set_reply(message_id.into(), reply_id.into()));
+		sqlx::query!(
+			r#"UPDATE count_events SET reply_id = $2 WHERE message_id = $1"#,
+			i64::from(message_id),
+			i64::from(reply_id),
+		)
+		.execute(pool)
+		.await
+		.map(|_| ())
+	}
+
+	/// Rolls back the counts of deleted messages, returns how many events were removed
+	pub async fn remove_messages(
+		&self,
+		message_ids: impl IntoIterator<Item = impl Into<PsqlU64>>,
+	) -> sqlx::Result<u64> {
+		let message_ids: Vec<i64> = message_ids
+			.into_iter()
+			.map(|id| i64::from(id.into()))
+			.collect();
+		let pool = postgres!(self, remove_messages(&message_ids));
+
+		let mut tx = pool.begin().await?;
+		let removed = sqlx::query!(
+			r#"WITH deleted AS (
+				DELETE FROM count_events WHERE message_id = ANY($1)
+				RETURNING user_id, server_id, emote
+			)
+			SELECT user_id, server_id, emote, COUNT(*)::integer AS "count!"
+			FROM deleted
+			GROUP BY user_id, server_id, emote"#,
+			&message_ids,
+		)
+		.fetch_all(&mut *tx)
+		.await?;
+
+		for row in &removed {
+			roll_back_count(&mut tx, row.user_id, row.server_id, &row.emote, row.count).await?;
+		}
+		tx.commit().await?;
+
+		Ok(removed.iter().map(|row| row.count as u64).sum())
+	}
+	pub async fn user_data(&self, user_id: impl Into<PsqlU64>) -> sqlx::Result<UserData> {
+		let user_id = user_id.into();
+		let pool = postgres!(self, user_data(user_id.into()));
+		let options = sqlx::query_as!(
+			UserOptions,
+			r#"SELECT opt_out, silent, global, username FROM options WHERE user_id = $1"#,
+			i64::from(user_id),
+		)
+		.fetch_optional(pool)
+		.await?;
+		let server_options = sqlx::query_as!(
+			UserServerOptions,
+			r#"SELECT server_id, opt_out, silent, silent_set FROM user_server_options
+			WHERE user_id = $1
+			ORDER BY server_id"#,
+			i64::from(user_id),
+		)
+		.fetch_all(pool)
+		.await?;
+		let counts = sqlx::query_as!(
+			ServerCount,
+			r#"SELECT server_id, emote, count FROM counter
+			WHERE user_id = $1
+			ORDER BY server_id, emote"#,
+			i64::from(user_id),
+		)
+		.fetch_all(pool)
+		.await?;
+		let events = sqlx::query!(
+			r#"SELECT server_id, channel_id, message_id, reply_id, emote, variant, matched,
+			to_char(created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"Z"') AS "created_at!"
+			FROM count_events
+			WHERE user_id = $1
+			ORDER BY id"#,
+			i64::from(user_id),
+		)
+		.fetch_all(pool)
+		.await?
+		.into_iter()
+		.map(|row| CountEvent {
+			server_id: row.server_id.into(),
+			channel_id: row.channel_id.into(),
+			message_id: row.message_id.into(),
+			reply_id: row.reply_id.map(Into::into),
+			emote: row.emote,
+			variant: row.variant,
+			matched: row.matched,
+			created_at: row.created_at,
+		})
+		.collect();
+
+		let audit = sqlx::query!(
+			r#"SELECT server_id, actor_id, user_id, action, target, old_value, new_value, reason,
+			EXTRACT(EPOCH FROM created_at)::bigint AS "created_at!"
+			FROM audit_log
+			WHERE actor_id = $1 OR user_id = $1
+			ORDER BY id"#,
+			i64::from(user_id),
+		)
+		.fetch_all(pool)
+		.await?
+		.into_iter()
+		.map(|row| AuditEntry {
+			server_id: row.server_id.into(),
+			actor_id: row.actor_id.map(Into::into),
+			user_id: row.user_id.map(Into::into),
+			action: row.action,
+			target: row.target,
+			old_value: row.old_value,
+			new_value: row.new_value,
+			reason: row.reason,
+			created_at: row.created_at,
+		})
+		.collect();
+
+		Ok(UserData {
+			user_id,
+			options,
+			server_options,
+			counts,
+			events,
+			audit,
+		})
+	}
+
+	/// Deletes everything stored about the user in the server, or everywhere without a server.
+	/// Only an opt-out is kept, so the user isn't counted again. The deletion is recorded in
+	/// every server it affected, without the user and without the actor if it's the user
+	pub async fn delete_user_data(
+		&self,
+		actor_id: impl Into<PsqlU64>,
+		user_id: impl Into<PsqlU64>,
+		server_id: Option<PsqlU64>,
+	) -> sqlx::Result<()> {
+		let user_id = user_id.into();
+		self.remove_user_data(actor_id.into(), user_id, server_id)
+			.await?;
+		self.invalidate_options(Some(user_id), server_id);
+		Ok(())
+	}
+	async fn remove_user_data(
+		&self,
+		actor_id: PsqlU64,
+		user_id: PsqlU64,
+		server_id: Option<PsqlU64>,
+	) -> sqlx::Result<()> {
+		let actor_id = (actor_id != user_id).then_some(i64::from(actor_id));
+		let user_id = i64::from(user_id);
+		let server_id = server_id.map(i64::from);
+		let pool = postgres!(self, delete_user_data(actor_id, user_id, server_id));
+		let mut tx = pool.begin().await?;
+		sqlx::query!(
+			r#"INSERT INTO audit_log (server_id, actor_id, action)
+			SELECT server_id, $3::bigint, 'delete_user_data' FROM (
+				SELECT $2::bigint AS server_id WHERE $2 IS NOT NULL
+				UNION SELECT server_id FROM counter WHERE user_id = $1 AND $2 IS NULL
+				UNION SELECT server_id FROM count_events WHERE user_id = $1 AND $2 IS NULL
+				UNION SELECT server_id FROM user_server_options WHERE user_id = $1 AND $2 IS NULL
+				UNION SELECT server_id FROM audit_log
+				WHERE (actor_id = $1 OR user_id = $1) AND $2 IS NULL
+			) AS servers
+			ORDER BY server_id"#,
+			user_id,
+			server_id,
+			actor_id,
+		)
+		.execute(&mut *tx)
+		.await?;
+		// changes only about the user go, the ones made to the server just lose who made them
+		sqlx::query!(
+			r#"DELETE FROM audit_log
+			WHERE actor_id = $1 AND user_id = $1 AND (server_id = $2 OR $2 IS NULL)"#,
+			user_id,
+			server_id,
+		)
+		.execute(&mut *tx)
+		.await?;
+		sqlx::query!(
+			r#"UPDATE audit_log
+			SET actor_id = NULLIF(actor_id, $1), user_id = NULLIF(user_id, $1)
+			WHERE (actor_id = $1 OR user_id = $1) AND (server_id = $2 OR $2 IS NULL)"#,
+			user_id,
+			server_id,
+		)
+		.execute(&mut *tx)
+		.await?;
+		sqlx::query!(
+			r#"DELETE FROM count_events WHERE user_id = $1 AND (server_id = $2 OR $2 IS NULL)"#,
+			user_id,
+			server_id,
+		)
+		.execute(&mut *tx)
+		.await?;
+		sqlx::query!(
+			r#"DELETE FROM counter WHERE user_id = $1 AND (server_id = $2 OR $2 IS NULL)"#,
+			user_id,
+			server_id,
+		)
+		.execute(&mut *tx)
+		.await?;
+		sqlx::query!(
+			r#"DELETE FROM user_server_options WHERE user_id = $1 AND (server_id = $2 OR $2 IS NULL)"#,
+			user_id,
+			server_id,
+		)
+		.execute(&mut *tx)
+		.await?;
+		sqlx::query!(
+			r#"INSERT INTO user_server_options (user_id, server_id, opt_out)
+			SELECT $1, $2, true WHERE $2::bigint IS NOT NULL"#,
+			user_id,
+			server_id,
+		)
+		.execute(&mut *tx)
+		.await?;
+		sqlx::query!(
+			r#"INSERT INTO options (user_id, opt_out)
+			SELECT $1, true WHERE $2::bigint IS NULL
+			ON CONFLICT (user_id) DO UPDATE
+			SET opt_out = true, silent = NULL, global = false, username = NULL"#,
+			user_id,
+			server_id,
+		)
+		.execute(&mut *tx)
+		.await?;
+		tx.commit().await
+	}
+
+	/// Records a change made through a command in the server
+	pub async fn audit(
+		&self,
+		actor_id: impl Into<PsqlU64>,
+		server_id: impl Into<PsqlU64>,
+		change: Change<'_>,
+	) -> sqlx::Result<()> {
+		let actor_id = i64::from(actor_id.into());
+		let server_id = i64::from(server_id.into());
+		let pool = postgres!(self, audit(actor_id, server_id, change));
+		insert_audit(pool, Some(actor_id), server_id, change).await
+	}
+	/// The latest changes made in the server, newest first
+	pub async fn audit_log(
+		&self,
+		server_id: impl Into<PsqlU64>,
+		limit: u32,
+	) -> sqlx::Result<Vec<AuditEntry>> {
+		let server_id = server_id.into();
+		let pool = postgres!(self, audit_log(server_id.into(), limit));
+		Ok(sqlx::query!(
+			r#"SELECT server_id, actor_id, user_id, action, target, old_value, new_value, reason,
+			EXTRACT(EPOCH FROM created_at)::bigint AS "created_at!"
+			FROM audit_log
+			WHERE server_id = $1
+			ORDER BY id DESC
+			LIMIT $2"#,
+			i64::from(server_id),
+			i64::from(limit),
+		)
+		.fetch_all(pool)
+		.await?
+		.into_iter()
+		.map(|row| AuditEntry {
+			server_id: row.server_id.into(),
+			actor_id: row.actor_id.map(Into::into),
+			user_id: row.user_id.map(Into::into),
+			action: row.action,
+			target: row.target,
+			old_value: row.old_value,
+			new_value: row.new_value,
+			reason: row.reason,
+			created_at: row.created_at,
+		})
+		.collect())
+	}
+
+	/// Sets the user's count of the emote in the server and clears its history like `reset_counts`,
+	/// returns the count it had before
+	pub async fn set_count(
+		&self,
+		actor_id: impl Into<PsqlU64>,
+		server_id: impl Into<PsqlU64>,
+		user_id: impl Into<PsqlU64>,
+		emote: &str,
+		count: u32,
+		reason: &str,
+	) -> sqlx::Result<u32> {
+		let actor_id = i64::from(actor_id.into());
+		let server_id = i64::from(server_id.into());
+		let user_id = i64::from(user_id.into());
+		let pool = postgres!(
+			self,
+			set_count(actor_id, server_id, user_id, emote, count, reason)
+		);
+		let mut tx = pool.begin().await?;
+		let old_count = sqlx::query_scalar!(
+			r#"SELECT count FROM counter
+			WHERE user_id = $1 AND server_id = $2 AND emote = $3
+			FOR UPDATE"#,
+			user_id,
+			server_id,
+			emote,
+		)
+		.fetch_optional(&mut *tx)
+		.await?
+		.unwrap_or_default();
+		// deleting an old message would otherwise take from the count that was set
+		sqlx::query!(
+			r#"DELETE FROM count_events WHERE user_id = $1 AND server_id = $2 AND emote = $3"#,
+			user_id,
+			server_id,
+			emote,
+		)
+		.execute(&mut *tx)
+		.await?;
+		if count == 0 {
+			// rows can't hold zero because of counter_count_check
+			sqlx::query!(
+				r#"DELETE FROM counter WHERE user_id = $1 AND server_id = $2 AND emote = $3"#,
+				user_id,
+				server_id,
+				emote,
+			)
+			.execute(&mut *tx)
+			.await?;
+		} else {
+			sqlx::query!(
+				r#"INSERT INTO counter (user_id, server_id, emote, count) VALUES ($1, $2, $3, $4)
 				ON CONFLICT (user_id, server_id, emote) DO
-				UPDATE SET count = counter.count + 1
-				RETURNING count"#,
-				i64::from(user_id),
-				i64::from(server_id),
+				UPDATE SET count = EXCLUDED.count"#,
+				user_id,
+				server_id,
 				emote,
+				count as i32,
 			)
-			.fetch_one(&self.pool)
-			.await? as u32,
-		))
+			.execute(&mut *tx)
+			.await?;
+		}
+		let change = Change {
+			action: "set_count",
+			user_id: Some(user_id.into()),
+			target: Some(format!("*{emote}*")),
+			old_value: Some(old_count.to_string()),
+			new_value: Some(count.to_string()),
+			reason: Some(reason),
+		};
+		insert_audit(&mut *tx, Some(actor_id), server_id, change).await?;
+		tx.commit().await?;
+
+		Ok(old_count as u32)
+	}
+	/// Removes the counts and their history of the user in the server, or of everyone without a user,
+	/// returns how many counts were removed
+	pub async fn reset_counts(
+		&self,
+		actor_id: impl Into<PsqlU64>,
+		server_id: impl Into<PsqlU64>,
+		user_id: Option<PsqlU64>,
+		reason: &str,
+	) -> sqlx::Result<u64> {
+		let actor_id = i64::from(actor_id.into());
+		let server_id = i64::from(server_id.into());
+		let user_id = user_id.map(i64::from);
+		let pool = postgres!(self, reset_counts(actor_id, server_id, user_id, reason));
+		let mut tx = pool.begin().await?;
+		// the events go too, so deleting an old message doesn't take from the new counts
+		sqlx::query!(
+			r#"DELETE FROM count_events WHERE server_id = $1 AND (user_id = $2 OR $2 IS NULL)"#,
+			server_id,
+			user_id,
+		)
+		.execute(&mut *tx)
+		.await?;
+		let removed = sqlx::query_scalar!(
+			r#"WITH deleted AS (
+				DELETE FROM counter WHERE server_id = $1 AND (user_id = $2 OR $2 IS NULL)
+				RETURNING count
+			)
+			SELECT COALESCE(SUM(count), 0) AS "removed!" FROM deleted"#,
+			server_id,
+			user_id,
+		)
+		.fetch_one(&mut *tx)
+		.await?;
+		let change = Change {
+			action: if user_id.is_some() {
+				"reset_user"
+			} else {
+				"reset_server"
+			},
+			user_id: user_id.map(Into::into),
+			target: None,
+			old_value: Some(removed.to_string()),
+			new_value: Some(0.to_string()),
+			reason: Some(reason),
+		};
+		insert_audit(&mut *tx, Some(actor_id), server_id, change).await?;
+		tx.commit().await?;
+
+		Ok(removed as u64)
 	}
+
 	pub async fn get_user_counts(
 		&self,
 		user_id: impl Into<PsqlU64>,
 	) -> sqlx::Result<Vec<UserCount>> {
 		let user_id = user_id.into();
+		let pool = postgres!(self, user_counts(user_id.into(), None));
 		sqlx::query_as!(
 			UserCount,
 			r#"SELECT
 			emote, SUM(count) as "count!"
 			FROM counter
-			WHERE user_id = $1
+			WHERE user_id = $1 AND NOT opted_out(user_id, server_id)
 			GROUP BY emote
 			ORDER BY "count!" DESC"#,
 			i64::from(user_id)
 		)
-		.fetch_all(&self.pool)
+		.fetch_all(pool)
 		.await
 	}
 	pub async fn get_user_server_counts(
@@ -98,127 +1238,941 @@ impl DatabaseHandler {
 	) -> sqlx::Result<Vec<UserCount>> {
 		let user_id = user_id.into();
 		let server_id = server_id.into();
+		let pool = postgres!(self, user_counts(user_id.into(), Some(server_id.into())));
 		sqlx::query_as!(
 			UserCount,
 			r#"SELECT
 			emote, SUM(count) as "count!"
 			FROM counter WHERE user_id = $1 AND server_id = $2
+			AND NOT opted_out(user_id, server_id)
 			GROUP BY emote
 			ORDER BY "count!" DESC"#,
 			i64::from(user_id),
 			i64::from(server_id),
 		)
-		.fetch_all(&self.pool)
+		.fetch_all(pool)
 		.await
 	}
 
-	pub async fn set_opt_out(&self, user_id: impl Into<PsqlU64>, value: bool) -> sqlx::Result<()> {
+	/// Sets the user's option in the server, or their global one without a server.
+	/// Returns the option it had before, if it was set
+	pub async fn set_opt_out(
+		&self,
+		user_id: impl Into<PsqlU64>,
+		server_id: Option<PsqlU64>,
+		value: bool,
+	) -> sqlx::Result<Option<bool>> {
+		let user_id = user_id.into();
+		let old = self.write_opt_out(user_id, server_id, value).await?;
+		self.invalidate_options(Some(user_id), server_id);
+		Ok(old)
+	}
+	async fn write_opt_out(
+		&self,
+		user_id: PsqlU64,
+		server_id: Option<PsqlU64>,
+		value: bool,
+	) -> sqlx::Result<Option<bool>> {
+		let pool = postgres!(
+			self,
+			set_opt_out(user_id.into(), server_id.map(i64::from), value)
+		);
+		match server_id {
+			Some(server_id) => {
+				sqlx::query_scalar!(
+					r#"WITH old AS (
+					SELECT opt_out FROM user_server_options WHERE user_id = $1 AND server_id = $2
+				)
+				INSERT INTO user_server_options (user_id, server_id, opt_out) VALUES ($1, $2, $3)
+				ON CONFLICT (user_id, server_id) DO UPDATE
+				SET opt_out = EXCLUDED.opt_out
+				RETURNING (SELECT opt_out FROM old)"#,
+					i64::from(user_id),
+					i64::from(server_id),
+					value,
+				)
+				.fetch_one(pool)
+				.await
+			}
+			None => {
+				sqlx::query_scalar!(
+					r#"WITH old AS (SELECT opt_out FROM options WHERE user_id = $1)
+				INSERT INTO options (user_id, opt_out) VALUES ($1, $2)
+				ON CONFLICT (user_id) DO UPDATE
+				SET opt_out = EXCLUDED.opt_out
+				RETURNING (SELECT opt_out FROM old)"#,
+					i64::from(user_id),
+					value,
+				)
+				.fetch_one(pool)
+				.await
+			}
+		}
+	}
+	pub async fn set_global(
+		&self,
+		user_id: impl Into<PsqlU64>,
+		username: Option<&str>,
+	) -> sqlx::Result<()> {
 		let user_id = user_id.into();
+		let pool = postgres!(self, set_global(user_id.into(), username));
 		sqlx::query!(
-			r#"INSERT INTO options (user_id, opt_out) VALUES ($1, $2)
+			r#"INSERT INTO options (user_id, global, username) VALUES ($1, $2, $3)
 			ON CONFLICT (user_id) DO UPDATE
-			SET opt_out = EXCLUDED.opt_out"#,
+			SET global = EXCLUDED.global, username = EXCLUDED.username"#,
 			i64::from(user_id),
-			value,
+			username.is_some(),
+			username,
 		)
-		.execute(&self.pool)
+		.execute(pool)
 		.await
 		.map(|_| ())
 	}
-	pub async fn is_opt_out(&self, user_id: impl Into<PsqlU64>) -> sqlx::Result<bool> {
+	/// The server's setting if the user has one there, otherwise their global one.
+	/// Without a server, only the global one
+	pub async fn is_opt_out(
+		&self,
+		user_id: impl Into<PsqlU64>,
+		server_id: Option<PsqlU64>,
+	) -> sqlx::Result<bool> {
 		let user_id = user_id.into();
+		let Some(server_id) = server_id else {
+			return self.load_opt_out(user_id, None).await;
+		};
+		let key = (user_id.0, server_id.0);
+		if let Some(opt_out) = self.cached_opt_out(key) {
+			return Ok(opt_out);
+		}
+
+		let generation = self.options_generation.load(Ordering::Acquire);
+		let opt_out = self.load_opt_out(user_id, Some(server_id)).await?;
+		self.cache_opt_out(key, generation, opt_out);
+		Ok(opt_out)
+	}
+	async fn load_opt_out(
+		&self,
+		user_id: PsqlU64,
+		server_id: Option<PsqlU64>,
+	) -> sqlx::Result<bool> {
+		let pool = postgres!(self, is_opt_out(user_id.into(), server_id.map(i64::from)));
 		sqlx::query_scalar!(
-			r#"SELECT EXISTS(
-				SELECT * FROM options WHERE user_id = $1 AND opt_out
-			) AS "exists!""#,
+			r#"SELECT opted_out($1, $2) AS "opt_out!""#,
 			i64::from(user_id),
+			server_id.map(i64::from),
 		)
-		.fetch_one(&self.pool)
+		.fetch_one(pool)
 		.await
 	}
 
+	/// Returns the value it had before
 	pub async fn mute_all(
 		&self,
 		server_id: impl Into<PsqlU64>,
 		value: Option<u32>,
-	) -> sqlx::Result<()> {
+	) -> sqlx::Result<Option<u32>> {
 		let server_id = server_id.into();
-		sqlx::query!(
-			r#"INSERT INTO server_options (server_id, mute_all) VALUES ($1, $2)
+		let old = self.write_mute_all(server_id, value).await?;
+		self.invalidate_options(None, Some(server_id));
+		Ok(old)
+	}
+	async fn write_mute_all(
+		&self,
+		server_id: PsqlU64,
+		value: Option<u32>,
+	) -> sqlx::Result<Option<u32>> {
+		let pool = postgres!(self, mute_all(server_id.into(), value));
+		sqlx::query_scalar!(
+			r#"WITH old AS (SELECT mute_all FROM server_options WHERE server_id = $1)
+		INSERT INTO server_options (server_id, mute_all) VALUES ($1, $2)
 		ON CONFLICT (server_id) DO UPDATE
-		SET mute_all = EXCLUDED.mute_all"#,
+		SET mute_all = EXCLUDED.mute_all
+		RETURNING (SELECT mute_all FROM old)"#,
 			i64::from(server_id),
 			value.map(|v| v as i32),
 		)
-		.execute(&self.pool)
+		.fetch_one(pool)
 		.await
-		.map(|_| ())
+		.map(|old| old.map(|v| v as u32))
+	}
+	/// Returns the mode it had before
+	pub async fn set_match_mode(
+		&self,
+		server_id: impl Into<PsqlU64>,
+		mode: MatchMode,
+	) -> sqlx::Result<MatchMode> {
+		let server_id = server_id.into();
+		let old = match &self.pool {
+			Pool::Postgres(pool) => sqlx::query_scalar!(
+				r#"WITH old AS (SELECT match_mode FROM server_options WHERE server_id = $1)
+				INSERT INTO server_options (server_id, match_mode) VALUES ($1, $2)
+				ON CONFLICT (server_id) DO UPDATE
+				SET match_mode = EXCLUDED.match_mode
+				RETURNING (SELECT match_mode FROM old)"#,
+				i64::from(server_id),
+				mode.as_str(),
+			)
+			.fetch_one(pool)
+			.await?
+			.as_deref()
+			.and_then(MatchMode::from_choice)
+			.unwrap_or_default(),
+			#[cfg(feature = "sqlite")]
+			Pool::Sqlite(pool) => sqlite::set_match_mode(pool, server_id.into(), mode).await?,
+		};
+		self.invalidate_matcher(server_id);
+		Ok(old)
 	}
+	pub async fn match_mode(&self, server_id: impl Into<PsqlU64>) -> sqlx::Result<MatchMode> {
+		let server_id = server_id.into();
+		let pool = postgres!(self, match_mode(server_id.into()));
+		let mode = sqlx::query_scalar!(
+			r#"SELECT match_mode FROM server_options WHERE server_id = $1"#,
+			i64::from(server_id),
+		)
+		.fetch_optional(pool)
+		.await?;
+		Ok(mode
+			.as_deref()
+			.and_then(MatchMode::from_choice)
+			.unwrap_or_default())
+	}
+	/// Returns the language it had before
+	pub async fn set_language(
+		&self,
+		server_id: impl Into<PsqlU64>,
+		language: Language,
+	) -> sqlx::Result<Language> {
+		let server_id = server_id.into();
+		let old = self.write_language(server_id, language).await?;
+		self.invalidate_settings(server_id);
+		Ok(old)
+	}
+	async fn write_language(
+		&self,
+		server_id: PsqlU64,
+		language: Language,
+	) -> sqlx::Result<Language> {
+		let pool = postgres!(self, set_language(server_id.into(), language));
+		let old = sqlx::query_scalar!(
+			r#"WITH old AS (SELECT language FROM server_options WHERE server_id = $1)
+			INSERT INTO server_options (server_id, language) VALUES ($1, $2)
+			ON CONFLICT (server_id) DO UPDATE
+			SET language = EXCLUDED.language
+			RETURNING (SELECT language FROM old)"#,
+			i64::from(server_id),
+			language.as_str(),
+		)
+		.fetch_one(pool)
+		.await?;
+		Ok(old.map(Into::into).unwrap_or_default())
+	}
+	pub async fn language(&self, server_id: impl Into<PsqlU64>) -> sqlx::Result<Language> {
+		Ok(self.server_settings(server_id.into()).await?.language)
+	}
+	async fn load_language(&self, server_id: PsqlU64) -> sqlx::Result<Language> {
+		let pool = postgres!(self, language(server_id.into()));
+		let language = sqlx::query_scalar!(
+			r#"SELECT language FROM server_options WHERE server_id = $1"#,
+			i64::from(server_id),
+		)
+		.fetch_optional(pool)
+		.await?;
+		Ok(language.map(Into::into).unwrap_or_default())
+	}
+	/// Sets whether x3s are counted in the channel, `None` goes back to the default.
+	/// Returns the mode it had before
+	pub async fn set_channel_mode(
+		&self,
+		server_id: impl Into<PsqlU64>,
+		channel_id: impl Into<PsqlU64>,
+		mode: Option<ChannelMode>,
+	) -> sqlx::Result<Option<ChannelMode>> {
+		let server_id = server_id.into();
+		let old = self
+			.write_channel_mode(server_id, channel_id.into(), mode)
+			.await?;
+		self.invalidate_settings(server_id);
+		Ok(old)
+	}
+	async fn write_channel_mode(
+		&self,
+		server_id: PsqlU64,
+		channel_id: PsqlU64,
+		mode: Option<ChannelMode>,
+	) -> sqlx::Result<Option<ChannelMode>> {
+		let pool = postgres!(
+			self,
+			set_channel_mode(server_id.into(), channel_id.into(), mode)
+		);
+		let old = match mode {
+			Some(mode) => {
+				sqlx::query_scalar!(
+					r#"WITH old AS (
+						SELECT mode FROM channel_options WHERE server_id = $1 AND channel_id = $2
+					)
+					INSERT INTO channel_options (server_id, channel_id, mode) VALUES ($1, $2, $3)
+					ON CONFLICT (server_id, channel_id) DO UPDATE
+					SET mode = EXCLUDED.mode
+					RETURNING (SELECT mode FROM old)"#,
+					i64::from(server_id),
+					i64::from(channel_id),
+					mode.as_str(),
+				)
+				.fetch_one(pool)
+				.await?
+			}
+			None => {
+				sqlx::query_scalar!(
+					r#"DELETE FROM channel_options WHERE server_id = $1 AND channel_id = $2
+					RETURNING mode"#,
+					i64::from(server_id),
+					i64::from(channel_id),
+				)
+				.fetch_optional(pool)
+				.await?
+			}
+		};
+		Ok(old.map(Into::into))
+	}
+	pub async fn channel_options(
+		&self,
+		server_id: impl Into<PsqlU64>,
+	) -> sqlx::Result<Vec<ChannelOption>> {
+		let server_id = server_id.into();
+		let pool = postgres!(self, channel_options(server_id.into()));
+		sqlx::query_as!(
+			ChannelOption,
+			r#"SELECT channel_id, mode FROM channel_options
+			WHERE server_id = $1
+			ORDER BY mode, channel_id"#,
+			i64::from(server_id),
+		)
+		.fetch_all(pool)
+		.await
+	}
+	/// `parent_id` is the channel a thread is in
+	pub async fn is_channel_counted(
+		&self,
+		server_id: impl Into<PsqlU64>,
+		channel_id: impl Into<PsqlU64>,
+		parent_id: Option<PsqlU64>,
+	) -> sqlx::Result<bool> {
+		let settings = self.server_settings(server_id.into()).await?;
+		Ok(settings.is_channel_counted(channel_id.into().0, parent_id.map(|id| id.0)))
+	}
+	/// Sets the user's option in the server, or their global one without a server.
+	/// Returns the option it had before, `None` if it wasn't set in the server
 	pub async fn set_silent(
 		&self,
 		user_id: impl Into<PsqlU64>,
+		server_id: Option<PsqlU64>,
 		value: Option<u32>,
-	) -> sqlx::Result<()> {
+	) -> sqlx::Result<Option<Option<u32>>> {
 		let user_id = user_id.into();
-		sqlx::query!(
-			r#"INSERT INTO options (user_id, silent) VALUES ($1, $2)
-			ON CONFLICT (user_id) DO UPDATE
-			SET silent = EXCLUDED.silent"#,
-			i64::from(user_id),
-			value.map(|v| v as i32),
+		let old = self.write_silent(user_id, server_id, value).await?;
+		self.invalidate_options(Some(user_id), server_id);
+		Ok(old)
+	}
+	async fn write_silent(
+		&self,
+		user_id: PsqlU64,
+		server_id: Option<PsqlU64>,
+		value: Option<u32>,
+	) -> sqlx::Result<Option<Option<u32>>> {
+		let pool = postgres!(
+			self,
+			set_silent(user_id.into(), server_id.map(i64::from), value)
+		);
+		let old = match server_id {
+			Some(server_id) => sqlx::query!(
+				r#"WITH old AS (
+					SELECT silent, silent_set FROM user_server_options WHERE user_id = $1 AND server_id = $2
+				)
+				INSERT INTO user_server_options (user_id, server_id, silent, silent_set) VALUES ($1, $2, $3, true)
+				ON CONFLICT (user_id, server_id) DO UPDATE
+				SET silent = EXCLUDED.silent, silent_set = true
+				RETURNING (SELECT silent FROM old) AS old, (SELECT silent_set FROM old) AS "set""#,
+				i64::from(user_id),
+				i64::from(server_id),
+				value.map(|v| v as i32),
+			)
+			.fetch_one(pool)
+			.await
+			.map(|row| row.set.unwrap_or(false).then_some(row.old))?,
+			// the global option is always set, not being silent is the default
+			None => Some(
+				sqlx::query_scalar!(
+					r#"WITH old AS (SELECT silent FROM options WHERE user_id = $1)
+					INSERT INTO options (user_id, silent) VALUES ($1, $2)
+					ON CONFLICT (user_id) DO UPDATE
+					SET silent = EXCLUDED.silent
+					RETURNING (SELECT silent FROM old)"#,
+					i64::from(user_id),
+					value.map(|v| v as i32),
+				)
+				.fetch_one(pool)
+				.await?,
+			),
+		};
+		Ok(old.map(|old| old.map(|v| v as u32)))
+	}
+	/// Whether the user opted out in the server, if it is cached
+	fn cached_opt_out(&self, key: (u64, u64)) -> Option<bool> {
+		let opt_out = self.opt_outs.read().unwrap().get(&key).copied();
+		let counter = match opt_out {
+			Some(_) => &self.options_hits,
+			None => &self.options_misses,
+		};
+		counter.fetch_add(1, Ordering::Relaxed);
+		opt_out
+	}
+	/// Caches what was loaded, unless the options changed since `generation`
+	fn cache_opt_out(&self, key: (u64, u64), generation: u64, opt_out: bool) {
+		let mut cached = self.opt_outs.write().unwrap();
+		if self.options_generation.load(Ordering::Acquire) == generation {
+			if cached.len() >= MAX_CACHED_OPT_OUTS {
+				cached.clear();
+			}
+			cached.insert(key, opt_out);
+		}
+	}
+	/// Drops the cached options of the user, or of everyone without one,
+	/// in the server, or in every server without one
+	fn invalidate_options(&self, user_id: Option<PsqlU64>, server_id: Option<PsqlU64>) {
+		let mut cached = self.opt_outs.write().unwrap();
+		self.options_generation.fetch_add(1, Ordering::Release);
+		cached.retain(|(user, server), _| {
+			!(user_id.is_none_or(|user_id| user_id.0 == *user)
+				&& server_id.is_none_or(|server_id| server_id.0 == *server))
+		});
+	}
+	pub fn cache_stats(&self) -> CacheStats {
+		CacheStats {
+			options_hits: self.options_hits.load(Ordering::Relaxed),
+			options_misses: self.options_misses.load(Ordering::Relaxed),
+			cached_options: self.opt_outs.read().unwrap().len(),
+			matcher_hits: self.matcher_hits.load(Ordering::Relaxed),
+			matcher_misses: self.matcher_misses.load(Ordering::Relaxed),
+			cached_matchers: self.matchers.read().unwrap().len(),
+			settings_hits: self.settings_hits.load(Ordering::Relaxed),
+			settings_misses: self.settings_misses.load(Ordering::Relaxed),
+			cached_servers: self.settings.read().unwrap().len(),
+		}
+	}
+	/// Drops everything cached about a server the bot left
+	pub fn forget_server(&self, server_id: impl Into<PsqlU64>) {
+		let server_id = server_id.into();
+		self.invalidate_matcher(server_id);
+		self.invalidate_settings(server_id);
+		self.invalidate_options(None, Some(server_id));
+	}
+
+	/// Returns `limit` rows of the emote's ranking (or every emote's for `*`) starting after `offset`,
+	/// or of the ranking by all emotes combined when there is no emote.
+	/// Without a server, ranks the users who appear globally across every server
+	pub async fn leaderboard(
+		&self,
+		server_id: Option<PsqlU64>,
+		emote: Option<&str>,
+		order: EmoteOrder,
+		period: Period,
+		offset: u32,
+		limit: u32,
+	) -> sqlx::Result<LeaderboardPage> {
+		let server_id = server_id.map(i64::from);
+		let pool = postgres!(
+			self,
+			leaderboard(server_id, emote, order, period, offset, limit)
+		);
+		// one extra row tells whether there is a next page
+		let (offset, end) = (i64::from(offset), i64::from(offset + limit));
+		let mut rows = match emote {
+			Some(emote) => {
+				// `*` ranks every emote, no other emote is matched as a pattern
+				let emote = (emote != "*").then_some(emote);
+				sqlx::query_as!(
+					LeaderboardRow,
+					r#"WITH counts AS (
+						SELECT user_id, emote, count FROM counter
+						WHERE (server_id = $1 OR $1 IS NULL) AND $5::text IS NULL
+						AND NOT opted_out(user_id, server_id)
+						UNION ALL
+						SELECT user_id, emote, 1 FROM count_events
+						WHERE (server_id = $1 OR $1 IS NULL) AND $5::text IS NOT NULL
+						AND NOT opted_out(user_id, server_id)
+						AND created_at >= date_trunc($5, now() AT TIME ZONE 'UTC') AT TIME ZONE 'UTC'
+					), totals AS (
+						SELECT user_id, emote, SUM(count)::integer AS count
+						FROM counts
+						WHERE $1 IS NOT NULL
+						OR user_id IN (SELECT user_id FROM options WHERE global)
+						GROUP BY user_id, emote
+					), ranked AS (
+						SELECT user_id, emote, count,
+						SUM(count) OVER (PARTITION BY emote) AS total,
+						DENSE_RANK() OVER (PARTITION BY emote ORDER BY count DESC) AS rank,
+						ROW_NUMBER() OVER (PARTITION BY emote ORDER BY count DESC, user_id) AS position
+						FROM totals WHERE emote = $2 OR $2::text IS NULL
+					)
+					SELECT
+						emote AS "emote!",
+						user_id AS "user_id!",
+						count AS "count!",
+						rank AS "rank!",
+						position AS "position!",
+						CASE WHEN $1 IS NULL THEN
+							(SELECT username FROM options WHERE options.user_id = ranked.user_id)
+						END AS username
+					FROM ranked
+					WHERE position > $3 AND position <= $4
+					ORDER BY CASE WHEN $6 = 'total' THEN total END DESC, emote, position ASC"#,
+					server_id,
+					emote,
+					offset,
+					end + 1,
+					period.trunc_field(),
+					order.as_str(),
+				)
+				.fetch_all(pool)
+				.await?
+			}
+			None => {
+				sqlx::query_as!(
+					LeaderboardRow,
+					r#"WITH counts AS (
+						SELECT user_id, count FROM counter
+						WHERE (server_id = $1 OR $1 IS NULL) AND $4::text IS NULL
+						AND NOT opted_out(user_id, server_id)
+						UNION ALL
+						SELECT user_id, 1 FROM count_events
+						WHERE (server_id = $1 OR $1 IS NULL) AND $4::text IS NOT NULL
+						AND NOT opted_out(user_id, server_id)
+						AND created_at >= date_trunc($4, now() AT TIME ZONE 'UTC') AT TIME ZONE 'UTC'
+					), totals AS (
+						SELECT user_id, SUM(count)::integer AS count
+						FROM counts
+						WHERE $1 IS NOT NULL
+						OR user_id IN (SELECT user_id FROM options WHERE global)
+						GROUP BY user_id
+					), ranked AS (
+						SELECT user_id, count,
+						DENSE_RANK() OVER (ORDER BY count DESC) AS rank,
+						ROW_NUMBER() OVER (ORDER BY count DESC, user_id) AS position
+						FROM totals
+					)
+					SELECT
+						'' AS "emote!",
+						user_id AS "user_id!",
+						count AS "count!",
+						rank AS "rank!",
+						position AS "position!",
+						CASE WHEN $1 IS NULL THEN
+							(SELECT username FROM options WHERE options.user_id = ranked.user_id)
+						END AS username
+					FROM ranked
+					WHERE position > $2 AND position <= $3
+					ORDER BY position ASC"#,
+					server_id,
+					offset,
+					end + 1,
+					period.trunc_field(),
+				)
+				.fetch_all(pool)
+				.await?
+			}
+		};
+
+		let has_next = rows.iter().any(|row| i64::from(row.position) > end);
+		rows.retain(|row| i64::from(row.position) <= end);
+		Ok(LeaderboardPage { rows, has_next })
+	}
+
+	pub async fn server_patterns(
+		&self,
+		server_id: impl Into<PsqlU64>,
+	) -> sqlx::Result<Vec<ServerPattern>> {
+		let server_id = server_id.into();
+		let pool = postgres!(self, server_patterns(server_id.into()));
+		sqlx::query_as!(
+			ServerPattern,
+			r#"SELECT emote, pattern, kind FROM server_patterns
+			WHERE server_id = $1
+			ORDER BY emote"#,
+			i64::from(server_id),
 		)
-		.execute(&self.pool)
+		.fetch_all(pool)
 		.await
-		.map(|_| ())
 	}
-	pub async fn verbose_level(
+	/// Returns the pattern the emote had before, if it had one
+	pub async fn add_pattern(
+		&self,
+		server_id: impl Into<PsqlU64>,
+		pattern: &ServerPattern,
+	) -> sqlx::Result<Option<String>> {
+		let server_id = server_id.into();
+		let old = match &self.pool {
+			Pool::Postgres(pool) => {
+				sqlx::query_scalar!(
+					r#"WITH old AS (SELECT pattern FROM server_patterns WHERE server_id = $1 AND emote = $2)
+				INSERT INTO server_patterns (server_id, emote, pattern, kind) VALUES ($1, $2, $3, $4)
+				ON CONFLICT (server_id, emote) DO UPDATE
+				SET pattern = EXCLUDED.pattern, kind = EXCLUDED.kind
+				RETURNING (SELECT pattern FROM old)"#,
+					i64::from(server_id),
+					&*pattern.emote,
+					&*pattern.pattern,
+					pattern.kind.as_str(),
+				)
+				.fetch_one(pool)
+				.await?
+			}
+			#[cfg(feature = "sqlite")]
+			Pool::Sqlite(pool) => sqlite::add_pattern(pool, server_id.into(), pattern).await?,
+		};
+		self.invalidate_matcher(server_id);
+		Ok(old)
+	}
+	/// Returns the removed pattern, if there was one
+	pub async fn remove_pattern(
+		&self,
+		server_id: impl Into<PsqlU64>,
+		emote: &str,
+	) -> sqlx::Result<Option<String>> {
+		let server_id = server_id.into();
+		let removed = match &self.pool {
+			Pool::Postgres(pool) => {
+				sqlx::query_scalar!(
+					r#"DELETE FROM server_patterns WHERE server_id = $1 AND emote = $2
+				RETURNING pattern"#,
+					i64::from(server_id),
+					emote,
+				)
+				.fetch_optional(pool)
+				.await?
+			}
+			#[cfg(feature = "sqlite")]
+			Pool::Sqlite(pool) => sqlite::remove_pattern(pool, server_id.into(), emote).await?,
+		};
+		self.invalidate_matcher(server_id);
+		Ok(removed)
+	}
+
+	pub async fn reply_templates(
+		&self,
+		server_id: impl Into<PsqlU64>,
+	) -> sqlx::Result<Vec<ReplyTemplate>> {
+		let settings = self.server_settings(server_id.into()).await?;
+		Ok(settings.reply_templates.clone())
+	}
+	async fn load_reply_templates(&self, server_id: PsqlU64) -> sqlx::Result<Vec<ReplyTemplate>> {
+		let pool = postgres!(self, reply_templates(server_id.into()));
+		sqlx::query_as!(
+			ReplyTemplate,
+			r#"SELECT emote, template FROM reply_templates
+			WHERE server_id = $1
+			ORDER BY emote"#,
+			i64::from(server_id),
+		)
+		.fetch_all(pool)
+		.await
+	}
+	/// Returns the template the emote had before, if it had one
+	pub async fn set_reply_template(
+		&self,
+		server_id: impl Into<PsqlU64>,
+		emote: &str,
+		template: &str,
+	) -> sqlx::Result<Option<String>> {
+		let server_id = server_id.into();
+		let old = self
+			.write_reply_template(server_id, emote, template)
+			.await?;
+		self.invalidate_settings(server_id);
+		Ok(old)
+	}
+	async fn write_reply_template(
+		&self,
+		server_id: PsqlU64,
+		emote: &str,
+		template: &str,
+	) -> sqlx::Result<Option<String>> {
+		let pool = postgres!(self, set_reply_template(server_id.into(), emote, template));
+		sqlx::query_scalar!(
+			r#"WITH old AS (SELECT template FROM reply_templates WHERE server_id = $1 AND emote = $2)
+			INSERT INTO reply_templates (server_id, emote, template) VALUES ($1, $2, $3)
+			ON CONFLICT (server_id, emote) DO UPDATE
+			SET template = EXCLUDED.template
+			RETURNING (SELECT template FROM old)"#,
+			i64::from(server_id),
+			emote,
+			template,
+		)
+		.fetch_one(pool)
+		.await
+	}
+	/// Returns the removed template, if there was one
+	pub async fn remove_reply_template(
+		&self,
+		server_id: impl Into<PsqlU64>,
+		emote: &str,
+	) -> sqlx::Result<Option<String>> {
+		let server_id = server_id.into();
+		let removed = self.delete_reply_template(server_id, emote).await?;
+		self.invalidate_settings(server_id);
+		Ok(removed)
+	}
+	async fn delete_reply_template(
+		&self,
+		server_id: PsqlU64,
+		emote: &str,
+	) -> sqlx::Result<Option<String>> {
+		let pool = postgres!(self, remove_reply_template(server_id.into(), emote));
+		sqlx::query_scalar!(
+			r#"DELETE FROM reply_templates WHERE server_id = $1 AND emote = $2
+			RETURNING template"#,
+			i64::from(server_id),
+			emote,
+		)
+		.fetch_optional(pool)
+		.await
+	}
+	/// The user's all time rank on the emote's leaderboard in the server
+	pub async fn user_rank(
 		&self,
 		user_id: impl Into<PsqlU64>,
 		server_id: impl Into<PsqlU64>,
-	) -> sqlx::Result<VerboseLevel> {
+		emote: &str,
+	) -> sqlx::Result<Option<i64>> {
 		let user_id = user_id.into();
 		let server_id = server_id.into();
+		let pool = postgres!(self, user_rank(user_id.into(), server_id.into(), emote));
 		sqlx::query_scalar!(
-			r#"SELECT COALESCE((SELECT mute_all FROM server_options WHERE server_id = $1), (SELECT silent FROM options WHERE user_id = $2))"#,
+			r#"SELECT rank AS "rank!" FROM (
+				SELECT user_id, DENSE_RANK() OVER (ORDER BY count DESC) AS rank
+				FROM counter WHERE server_id = $1 AND emote = $2
+				AND NOT opted_out(user_id, server_id)
+			) AS ranked
+			WHERE user_id = $3"#,
 			i64::from(server_id),
+			emote,
 			i64::from(user_id),
 		)
-		.fetch_one(&self.pool)
+		.fetch_optional(pool)
 		.await
-		.map(Into::into)
 	}
 
-	pub async fn leaderboard(
+	pub async fn emote_aliases(
 		&self,
 		server_id: impl Into<PsqlU64>,
-		top: impl Into<PsqlU64>,
-		mut emote: &str,
-	) -> sqlx::Result<Vec<LeaderboardRow>> {
-		let top = top.into();
+	) -> sqlx::Result<Vec<EmoteAlias>> {
 		let server_id = server_id.into();
-		if emote == "*" {
-			emote = "%";
-		}
+		let pool = postgres!(self, emote_aliases(server_id.into()));
 		sqlx::query_as!(
-			LeaderboardRow,
-			r#"WITH ranked AS (
-				SELECT user_id, emote, count,
-				DENSE_RANK() OVER (PARTITION BY emote ORDER BY count DESC) AS rank
-				FROM counter WHERE server_id = $1 AND emote LIKE $2
-			)
-			SELECT
-				emote,
-				user_id,
-				count,
-				rank AS "rank!"
-			FROM ranked
-			WHERE rank <= $3
-			ORDER BY emote, rank ASC"#,
+			EmoteAlias,
+			r#"SELECT alias, emote FROM emote_aliases
+			WHERE server_id = $1
+			ORDER BY emote, alias"#,
 			i64::from(server_id),
-			emote,
-			i64::from(top),
 		)
-		.fetch_all(&self.pool)
+		.fetch_all(pool)
 		.await
 	}
+	/// Makes `alias` count as `emote`, merging the counts the alias already has into the emote.
+	/// Returns the emote the alias counted as before, if it was one
+	pub async fn add_alias(
+		&self,
+		server_id: impl Into<PsqlU64>,
+		alias: &str,
+		emote: &str,
+	) -> sqlx::Result<Option<String>> {
+		let server_id = server_id.into();
+		let old = match &self.pool {
+			Pool::Postgres(pool) => Self::merge_alias(pool, server_id, alias, emote).await?,
+			#[cfg(feature = "sqlite")]
+			Pool::Sqlite(pool) => sqlite::add_alias(pool, server_id.into(), alias, emote).await?,
+		};
+		self.invalidate_matcher(server_id);
+		Ok(old)
+	}
+	async fn merge_alias(
+		pool: &PgPool,
+		server_id: PsqlU64,
+		alias: &str,
+		emote: &str,
+	) -> sqlx::Result<Option<String>> {
+		let mut tx = pool.begin().await?;
+		let old = sqlx::query_scalar!(
+			r#"WITH old AS (SELECT emote FROM emote_aliases WHERE server_id = $1 AND alias = $2)
+			INSERT INTO emote_aliases (server_id, alias, emote) VALUES ($1, $2, $3)
+			ON CONFLICT (server_id, alias) DO UPDATE
+			SET emote = EXCLUDED.emote
+			RETURNING (SELECT emote FROM old)"#,
+			i64::from(server_id),
+			alias,
+			emote,
+		)
+		.fetch_one(&mut *tx)
+		.await?;
+		// aliases of the alias now point to the new emote
+		sqlx::query!(
+			r#"UPDATE emote_aliases SET emote = $3 WHERE server_id = $1 AND emote = $2"#,
+			i64::from(server_id),
+			alias,
+			emote,
+		)
+		.execute(&mut *tx)
+		.await?;
+		sqlx::query!(
+			r#"INSERT INTO counter (user_id, server_id, emote, count)
+			SELECT user_id, server_id, $3, count FROM counter WHERE server_id = $1 AND emote = $2
+			ON CONFLICT (user_id, server_id, emote) DO UPDATE
+			SET count = counter.count + EXCLUDED.count"#,
+			i64::from(server_id),
+			alias,
+			emote,
+		)
+		.execute(&mut *tx)
+		.await?;
+		sqlx::query!(
+			r#"DELETE FROM counter WHERE server_id = $1 AND emote = $2"#,
+			i64::from(server_id),
+			alias,
+		)
+		.execute(&mut *tx)
+		.await?;
+		sqlx::query!(
+			r#"UPDATE count_events SET emote = $3 WHERE server_id = $1 AND emote = $2"#,
+			i64::from(server_id),
+			alias,
+			emote,
+		)
+		.execute(&mut *tx)
+		.await?;
+		tx.commit().await?;
+		Ok(old)
+	}
+	/// Returns the emote the alias counted as, if it was one
+	pub async fn remove_alias(
+		&self,
+		server_id: impl Into<PsqlU64>,
+		alias: &str,
+	) -> sqlx::Result<Option<String>> {
+		let server_id = server_id.into();
+		let removed = match &self.pool {
+			Pool::Postgres(pool) => {
+				sqlx::query_scalar!(
+					r#"DELETE FROM emote_aliases WHERE server_id = $1 AND alias = $2
+				RETURNING emote"#,
+					i64::from(server_id),
+					alias,
+				)
+				.fetch_optional(pool)
+				.await?
+			}
+			#[cfg(feature = "sqlite")]
+			Pool::Sqlite(pool) => sqlite::remove_alias(pool, server_id.into(), alias).await?,
+		};
+		self.invalidate_matcher(server_id);
+		Ok(removed)
+	}
+
+	/// Returns the compiled matcher for the server, building it on first use
+	pub async fn matcher(&self, server_id: impl Into<PsqlU64>) -> sqlx::Result<Arc<Matcher>> {
+		let server_id = server_id.into();
+		if let Some(matcher) = self.matchers.read().unwrap().get(&server_id.0) {
+			self.matcher_hits.fetch_add(1, Ordering::Relaxed);
+			return Ok(matcher.clone());
+		}
+		self.matcher_misses.fetch_add(1, Ordering::Relaxed);
+
+		let generation = self.matchers_generation.load(Ordering::Acquire);
+		let patterns = self.server_patterns(server_id).await?;
+		let aliases = self.emote_aliases(server_id).await?;
+		let mode = self.match_mode(server_id).await?;
+		let matcher = Arc::new(
+			Matcher::new(&patterns, &aliases, mode).unwrap_or_else(|why| {
+				eprintln!("Invalid patterns for server {server_id}: {why}");
+				Matcher::default()
+			}),
+		);
+		let mut cached = self.matchers.write().unwrap();
+		if self.matchers_generation.load(Ordering::Acquire) == generation {
+			if cached.len() >= MAX_CACHED_SERVERS {
+				cached.clear();
+			}
+			cached.insert(server_id.0, matcher.clone());
+		}
+		Ok(matcher)
+	}
+	fn invalidate_matcher(&self, server_id: PsqlU64) {
+		let mut cached = self.matchers.write().unwrap();
+		self.matchers_generation.fetch_add(1, Ordering::Release);
+		cached.remove(&server_id.0);
+	}
+
+	/// Returns the settings counting a message needs, loading them on first use
+	async fn server_settings(&self, server_id: PsqlU64) -> sqlx::Result<Arc<ServerSettings>> {
+		if let Some(settings) = self.settings.read().unwrap().get(&server_id.0) {
+			self.settings_hits.fetch_add(1, Ordering::Relaxed);
+			return Ok(settings.clone());
+		}
+		self.settings_misses.fetch_add(1, Ordering::Relaxed);
+
+		let generation = self.settings_generation.load(Ordering::Acquire);
+		let settings = Arc::new(ServerSettings {
+			language: self.load_language(server_id).await?,
+			reply_templates: self.load_reply_templates(server_id).await?,
+			channels: self.channel_options(server_id).await?,
+		});
+		let mut cached = self.settings.write().unwrap();
+		if self.settings_generation.load(Ordering::Acquire) == generation {
+			if cached.len() >= MAX_CACHED_SERVERS {
+				cached.clear();
+			}
+			cached.insert(server_id.0, settings.clone());
+		}
+		Ok(settings)
+	}
+	fn invalidate_settings(&self, server_id: PsqlU64) {
+		let mut cached = self.settings.write().unwrap();
+		self.settings_generation.fetch_add(1, Ordering::Release);
+		cached.remove(&server_id.0);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn settings(channels: &[(u64, ChannelMode)]) -> ServerSettings {
+		ServerSettings {
+			language: Language::default(),
+			reply_templates: Vec::new(),
+			channels: channels
+				.iter()
+				.map(|&(channel_id, mode)| ChannelOption {
+					channel_id: channel_id.into(),
+					mode,
+				})
+				.collect(),
+		}
+	}
+
+	#[test]
+	fn channels_are_counted_by_their_own_setting_then_their_parents() {
+		let blocked = settings(&[(1, ChannelMode::Block), (2, ChannelMode::Allow)]);
+		assert!(!blocked.is_channel_counted(1, None));
+		assert!(blocked.is_channel_counted(2, None));
+		// once a channel is allowed, only allowed ones are
+		assert!(!blocked.is_channel_counted(3, None));
+		// threads use their own setting first
+		assert!(blocked.is_channel_counted(2, Some(1)));
+		assert!(!blocked.is_channel_counted(1, Some(2)));
+		assert!(!blocked.is_channel_counted(4, Some(1)));
+		assert!(blocked.is_channel_counted(4, Some(2)));
+
+		let only_blocked = settings(&[(1, ChannelMode::Block)]);
+		assert!(only_blocked.is_channel_counted(3, None));
+		assert!(!only_blocked.is_channel_counted(4, Some(1)));
+	}
 }
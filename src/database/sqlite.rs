@@ -0,0 +1,1311 @@
+//! The queries of `DatabaseHandler` for SQLite, checked at runtime since the query macros
+//! only know the Postgres schema
+
+use std::collections::HashMap;
+
+use sqlx::{SqliteConnection, SqliteExecutor, SqlitePool};
+
+use crate::{
+	locale::Language,
+	matcher::{EmoteAlias, Found, MatchMode, PatternKind, ServerPattern},
+	template::ReplyTemplate,
+};
+
+use super::{
+	kept_times, AuditEntry, Change, ChannelMode, ChannelOption, CountEvent, CountedMessage,
+	EmoteOrder, LeaderboardPage, LeaderboardRow, MessageEvent, NewCount, Period, ServerCount,
+	UserCount, UserData, UserOptions, UserServerOptions,
+};
+
+/// `opted_out(user_id, server_id)` from the Postgres schema, for the row of `$table`
+macro_rules! opted_out {
+	($table:literal) => {
+		concat!(
+			"COALESCE(
+				(SELECT opt_out FROM user_server_options AS o
+					WHERE o.user_id = ",
+			$table,
+			".user_id AND o.server_id = ",
+			$table,
+			".server_id),
+				(SELECT opt_out FROM options AS o WHERE o.user_id = ",
+			$table,
+			".user_id),
+				false
+			)"
+		)
+	};
+}
+
+/// The unix timestamp the period started at like `date_trunc`, NULL for all time
+macro_rules! period_start {
+	($param:literal) => {
+		concat!(
+			"CASE ",
+			$param,
+			"
+				WHEN 'day' THEN unixepoch('now', 'start of day')
+				WHEN 'week' THEN unixepoch('now', 'weekday 0', '-6 days', 'start of day')
+				WHEN 'month' THEN unixepoch('now', 'start of month')
+			END"
+		)
+	};
+}
+
+async fn insert_audit(
+	executor: impl SqliteExecutor<'_>,
+	actor_id: Option<i64>,
+	server_id: i64,
+	change: Change<'_>,
+) -> sqlx::Result<()> {
+	sqlx::query(
+		r#"INSERT INTO audit_log (server_id, actor_id, user_id, action, target, old_value, new_value, reason)
+		VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)"#,
+	)
+	.bind(server_id)
+	.bind(actor_id)
+	.bind(change.user_id.map(i64::from))
+	.bind(change.action)
+	.bind(change.target)
+	.bind(change.old_value)
+	.bind(change.new_value)
+	.bind(change.reason)
+	.execute(executor)
+	.await
+	.map(|_| ())
+}
+
+/// Selects the columns of `AuditEntry` from audit_log
+macro_rules! audit_columns {
+	() => {
+		r#"SELECT server_id, actor_id, user_id, action, target, old_value, new_value, reason, created_at
+		FROM audit_log"#
+	};
+}
+type AuditRow = (
+	i64,
+	Option<i64>,
+	Option<i64>,
+	String,
+	Option<String>,
+	Option<String>,
+	Option<String>,
+	Option<String>,
+	i64,
+);
+fn audit_entries(rows: Vec<AuditRow>) -> Vec<AuditEntry> {
+	rows.into_iter()
+		.map(
+			|(
+				server_id,
+				actor_id,
+				user_id,
+				action,
+				target,
+				old_value,
+				new_value,
+				reason,
+				created_at,
+			)| {
+				AuditEntry {
+					server_id: server_id.into(),
+					actor_id: actor_id.map(Into::into),
+					user_id: user_id.map(Into::into),
+					action,
+					target,
+					old_value,
+					new_value,
+					reason,
+					created_at,
+				}
+			},
+		)
+		.collect()
+}
+
+/// How many of each emote were found, in the order they were first found
+fn added_counts<'a>(found: &[Found<'a>]) -> Vec<(&'a str, u32)> {
+	let mut added: Vec<(&str, u32)> = Vec::new();
+	for found in found {
+		match added.iter_mut().find(|(emote, _)| *emote == found.emote) {
+			Some((_, count)) => *count += 1,
+			None => added.push((found.emote, 1)),
+		}
+	}
+	added
+}
+
+pub async fn count_message(
+	pool: &SqlitePool,
+	user_id: i64,
+	server_id: i64,
+	channel_id: i64,
+	message_id: i64,
+	found: &[Found<'_>],
+) -> sqlx::Result<Option<CountedMessage>> {
+	let mut tx = pool.begin().await?;
+	let created_at = vec![None; found.len()];
+	let counted = insert_counts(
+		&mut tx,
+		user_id,
+		server_id,
+		channel_id,
+		message_id,
+		found,
+		&created_at,
+	)
+	.await?;
+	tx.commit().await?;
+	Ok(counted)
+}
+pub async fn recount_message(
+	pool: &SqlitePool,
+	user_id: i64,
+	server_id: i64,
+	channel_id: i64,
+	message_id: i64,
+	sent_at: i64,
+	found: &[Found<'_>],
+) -> sqlx::Result<Option<CountedMessage>> {
+	let mut tx = pool.begin().await?;
+	let removed = sqlx::query_as::<_, (i64, i64, String, i64)>(
+		r#"SELECT user_id, server_id, emote, created_at FROM count_events
+		WHERE message_id = ?1
+		ORDER BY id"#,
+	)
+	.bind(message_id)
+	.fetch_all(&mut *tx)
+	.await?;
+	sqlx::query(r#"DELETE FROM count_events WHERE message_id = ?1"#)
+		.bind(message_id)
+		.execute(&mut *tx)
+		.await?;
+
+	let mut removed_counts: HashMap<(i64, i64, &str), i32> = HashMap::new();
+	for (user_id, server_id, emote, _) in &removed {
+		*removed_counts
+			.entry((*user_id, *server_id, emote))
+			.or_default() += 1;
+	}
+	for ((user_id, server_id, emote), count) in removed_counts {
+		roll_back_count(&mut tx, user_id, server_id, emote, count).await?;
+	}
+
+	let created_at: Vec<_> = kept_times(
+		removed
+			.iter()
+			.map(|(_, _, emote, created_at)| (emote.as_str(), *created_at)),
+		found,
+	)
+	.into_iter()
+	.map(|kept| Some(kept.unwrap_or(sent_at)))
+	.collect();
+	let counted = insert_counts(
+		&mut tx,
+		user_id,
+		server_id,
+		channel_id,
+		message_id,
+		found,
+		&created_at,
+	)
+	.await?;
+	tx.commit().await?;
+	Ok(counted)
+}
+/// Checks the options, counts and records the events at their `created_at` or now
+async fn insert_counts(
+	conn: &mut SqliteConnection,
+	user_id: i64,
+	server_id: i64,
+	channel_id: i64,
+	message_id: i64,
+	found: &[Found<'_>],
+	created_at: &[Option<i64>],
+) -> sqlx::Result<Option<CountedMessage>> {
+	let (opt_out, silent) = sqlx::query_as::<_, (bool, Option<i32>)>(
+		r#"SELECT
+			COALESCE(
+				(SELECT opt_out FROM user_server_options WHERE server_id = ?1 AND user_id = ?2),
+				(SELECT opt_out FROM options WHERE user_id = ?2),
+				false
+			),
+			COALESCE(
+				(SELECT mute_all FROM server_options WHERE server_id = ?1),
+				CASE WHEN (
+					SELECT silent_set FROM user_server_options WHERE server_id = ?1 AND user_id = ?2
+				)
+				THEN (SELECT silent FROM user_server_options WHERE server_id = ?1 AND user_id = ?2)
+				ELSE (SELECT silent FROM options WHERE user_id = ?2) END
+			)"#,
+	)
+	.bind(server_id)
+	.bind(user_id)
+	.fetch_one(&mut *conn)
+	.await?;
+	if opt_out {
+		return Ok(None);
+	}
+
+	let mut counts = Vec::new();
+	for (emote, added) in added_counts(found) {
+		let count: i32 = sqlx::query_scalar(
+			r#"INSERT INTO counter (user_id, server_id, emote, count) VALUES (?1, ?2, ?3, ?4)
+			ON CONFLICT (user_id, server_id, emote) DO
+			UPDATE SET count = counter.count + excluded.count
+			RETURNING count"#,
+		)
+		.bind(user_id)
+		.bind(server_id)
+		.bind(emote)
+		.bind(added as i32)
+		.fetch_one(&mut *conn)
+		.await?;
+		counts.push(NewCount {
+			emote: emote.into(),
+			added,
+			count: count as u32,
+		});
+	}
+	for (found, created_at) in found.iter().zip(created_at) {
+		sqlx::query(
+			r#"INSERT INTO count_events (user_id, server_id, channel_id, message_id, emote, variant, matched, created_at)
+			VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, COALESCE(?8, unixepoch()))"#,
+		)
+		.bind(user_id)
+		.bind(server_id)
+		.bind(channel_id)
+		.bind(message_id)
+		.bind(found.emote)
+		.bind(found.variant)
+		.bind(found.text)
+		.bind(created_at)
+		.execute(&mut *conn)
+		.await?;
+	}
+	Ok(Some(CountedMessage {
+		counts,
+		verbose: silent.into(),
+	}))
+}
+pub async fn message_events(pool: &SqlitePool, message_id: i64) -> sqlx::Result<Vec<MessageEvent>> {
+	Ok(sqlx::query_as::<_, (String, Option<i64>)>(
+		r#"SELECT emote, reply_id FROM count_events WHERE message_id = ?1 ORDER BY id"#,
+	)
+	.bind(message_id)
+	.fetch_all(pool)
+	.await?
+	.into_iter()
+	.map(|(emote, reply_id)| MessageEvent {
+		emote: emote.into(),
+		reply_id: reply_id.map(Into::into),
+	})
+	.collect())
+}
+pub async fn set_reply(pool: &SqlitePool, message_id: i64, reply_id: i64) -> sqlx::Result<()> {
+	sqlx::query(r#"UPDATE count_events SET reply_id = ?2 WHERE message_id = ?1"#)
+		.bind(message_id)
+		.bind(reply_id)
+		.execute(pool)
+		.await
+		.map(|_| ())
+}
+
+pub async fn remove_messages(pool: &SqlitePool, message_ids: &[i64]) -> sqlx::Result<u64> {
+	let mut tx = pool.begin().await?;
+	let mut removed: HashMap<(i64, i64, String), i32> = HashMap::new();
+	for message_id in message_ids {
+		let deleted = sqlx::query_as::<_, (i64, i64, String)>(
+			r#"DELETE FROM count_events WHERE message_id = ?1
+			RETURNING user_id, server_id, emote"#,
+		)
+		.bind(message_id)
+		.fetch_all(&mut *tx)
+		.await?;
+		for key in deleted {
+			*removed.entry(key).or_default() += 1;
+		}
+	}
+
+	for ((user_id, server_id, emote), count) in &removed {
+		roll_back_count(&mut tx, *user_id, *server_id, emote, *count).await?;
+	}
+	tx.commit().await?;
+
+	Ok(removed.values().map(|count| *count as u64).sum())
+}
+/// Takes back `count` of the user's emote, removing the row when it would hit zero
+/// to respect counter_count_check
+async fn roll_back_count(
+	conn: &mut SqliteConnection,
+	user_id: i64,
+	server_id: i64,
+	emote: &str,
+	count: i32,
+) -> sqlx::Result<()> {
+	sqlx::query(
+		r#"DELETE FROM counter
+		WHERE user_id = ?1 AND server_id = ?2 AND emote = ?3 AND count <= ?4"#,
+	)
+	.bind(user_id)
+	.bind(server_id)
+	.bind(emote)
+	.bind(count)
+	.execute(&mut *conn)
+	.await?;
+	sqlx::query(
+		r#"UPDATE counter SET count = count - ?4
+		WHERE user_id = ?1 AND server_id = ?2 AND emote = ?3"#,
+	)
+	.bind(user_id)
+	.bind(server_id)
+	.bind(emote)
+	.bind(count)
+	.execute(conn)
+	.await
+	.map(|_| ())
+}
+pub async fn user_data(pool: &SqlitePool, user_id: i64) -> sqlx::Result<UserData> {
+	let options = sqlx::query_as::<_, (bool, Option<i32>, bool, Option<String>)>(
+		r#"SELECT opt_out, silent, global, username FROM options WHERE user_id = ?1"#,
+	)
+	.bind(user_id)
+	.fetch_optional(pool)
+	.await?
+	.map(|(opt_out, silent, global, username)| UserOptions {
+		opt_out,
+		silent,
+		global,
+		username,
+	});
+	let server_options = sqlx::query_as::<_, (i64, Option<bool>, Option<i32>, bool)>(
+		r#"SELECT server_id, opt_out, silent, silent_set FROM user_server_options
+		WHERE user_id = ?1
+		ORDER BY server_id"#,
+	)
+	.bind(user_id)
+	.fetch_all(pool)
+	.await?
+	.into_iter()
+	.map(
+		|(server_id, opt_out, silent, silent_set)| UserServerOptions {
+			server_id: server_id.into(),
+			opt_out,
+			silent,
+			silent_set,
+		},
+	)
+	.collect();
+	let counts = sqlx::query_as::<_, (i64, String, i32)>(
+		r#"SELECT server_id, emote, count FROM counter
+		WHERE user_id = ?1
+		ORDER BY server_id, emote"#,
+	)
+	.bind(user_id)
+	.fetch_all(pool)
+	.await?
+	.into_iter()
+	.map(|(server_id, emote, count)| ServerCount {
+		server_id: server_id.into(),
+		emote,
+		count,
+	})
+	.collect();
+	let events = sqlx::query_as::<_, (i64, i64, i64, Option<i64>, String, String, String, String)>(
+		r#"SELECT server_id, channel_id, message_id, reply_id, emote, variant, matched,
+		strftime('%Y-%m-%dT%H:%M:%S.000000Z', created_at, 'unixepoch')
+		FROM count_events
+		WHERE user_id = ?1
+		ORDER BY id"#,
+	)
+	.bind(user_id)
+	.fetch_all(pool)
+	.await?
+	.into_iter()
+	.map(
+		|(server_id, channel_id, message_id, reply_id, emote, variant, matched, created_at)| {
+			CountEvent {
+				server_id: server_id.into(),
+				channel_id: channel_id.into(),
+				message_id: message_id.into(),
+				reply_id: reply_id.map(Into::into),
+				emote,
+				variant,
+				matched,
+				created_at,
+			}
+		},
+	)
+	.collect();
+
+	let audit = audit_entries(
+		sqlx::query_as(concat!(
+			audit_columns!(),
+			r#"
+			WHERE actor_id = ?1 OR user_id = ?1
+			ORDER BY id"#
+		))
+		.bind(user_id)
+		.fetch_all(pool)
+		.await?,
+	);
+
+	Ok(UserData {
+		user_id: user_id.into(),
+		options,
+		server_options,
+		counts,
+		events,
+		audit,
+	})
+}
+
+pub async fn delete_user_data(
+	pool: &SqlitePool,
+	actor_id: Option<i64>,
+	user_id: i64,
+	server_id: Option<i64>,
+) -> sqlx::Result<()> {
+	let mut tx = pool.begin().await?;
+	sqlx::query(
+		r#"INSERT INTO audit_log (server_id, actor_id, action)
+		SELECT server_id, ?3, 'delete_user_data' FROM (
+			SELECT ?2 AS server_id WHERE ?2 IS NOT NULL
+			UNION SELECT server_id FROM counter WHERE user_id = ?1 AND ?2 IS NULL
+			UNION SELECT server_id FROM count_events WHERE user_id = ?1 AND ?2 IS NULL
+			UNION SELECT server_id FROM user_server_options WHERE user_id = ?1 AND ?2 IS NULL
+			UNION SELECT server_id FROM audit_log
+			WHERE (actor_id = ?1 OR user_id = ?1) AND ?2 IS NULL
+		)
+		ORDER BY server_id"#,
+	)
+	.bind(user_id)
+	.bind(server_id)
+	.bind(actor_id)
+	.execute(&mut *tx)
+	.await?;
+	for query in [
+		// changes only about the user go, the ones made to the server just lose who made them
+		r#"DELETE FROM audit_log
+		WHERE actor_id = ?1 AND user_id = ?1 AND (server_id = ?2 OR ?2 IS NULL)"#,
+		r#"UPDATE audit_log
+		SET actor_id = NULLIF(actor_id, ?1), user_id = NULLIF(user_id, ?1)
+		WHERE (actor_id = ?1 OR user_id = ?1) AND (server_id = ?2 OR ?2 IS NULL)"#,
+		r#"DELETE FROM count_events WHERE user_id = ?1 AND (server_id = ?2 OR ?2 IS NULL)"#,
+		r#"DELETE FROM counter WHERE user_id = ?1 AND (server_id = ?2 OR ?2 IS NULL)"#,
+		r#"DELETE FROM user_server_options WHERE user_id = ?1 AND (server_id = ?2 OR ?2 IS NULL)"#,
+		r#"INSERT INTO user_server_options (user_id, server_id, opt_out)
+		SELECT ?1, ?2, true WHERE ?2 IS NOT NULL"#,
+		r#"INSERT INTO options (user_id, opt_out)
+		SELECT ?1, true WHERE ?2 IS NULL
+		ON CONFLICT (user_id) DO UPDATE
+		SET opt_out = true, silent = NULL, global = false, username = NULL"#,
+	] {
+		sqlx::query(query)
+			.bind(user_id)
+			.bind(server_id)
+			.execute(&mut *tx)
+			.await?;
+	}
+	tx.commit().await
+}
+
+pub async fn audit(
+	pool: &SqlitePool,
+	actor_id: i64,
+	server_id: i64,
+	change: Change<'_>,
+) -> sqlx::Result<()> {
+	insert_audit(pool, Some(actor_id), server_id, change).await
+}
+pub async fn audit_log(
+	pool: &SqlitePool,
+	server_id: i64,
+	limit: u32,
+) -> sqlx::Result<Vec<AuditEntry>> {
+	Ok(audit_entries(
+		sqlx::query_as(concat!(
+			audit_columns!(),
+			r#"
+			WHERE server_id = ?1
+			ORDER BY id DESC
+			LIMIT ?2"#
+		))
+		.bind(server_id)
+		.bind(limit)
+		.fetch_all(pool)
+		.await?,
+	))
+}
+
+pub async fn set_count(
+	pool: &SqlitePool,
+	actor_id: i64,
+	server_id: i64,
+	user_id: i64,
+	emote: &str,
+	count: u32,
+	reason: &str,
+) -> sqlx::Result<u32> {
+	let mut tx = pool.begin().await?;
+	let old_count: i32 = sqlx::query_scalar(
+		r#"SELECT count FROM counter WHERE user_id = ?1 AND server_id = ?2 AND emote = ?3"#,
+	)
+	.bind(user_id)
+	.bind(server_id)
+	.bind(emote)
+	.fetch_optional(&mut *tx)
+	.await?
+	.unwrap_or_default();
+	// deleting an old message would otherwise take from the count that was set
+	sqlx::query(r#"DELETE FROM count_events WHERE user_id = ?1 AND server_id = ?2 AND emote = ?3"#)
+		.bind(user_id)
+		.bind(server_id)
+		.bind(emote)
+		.execute(&mut *tx)
+		.await?;
+	if count == 0 {
+		// rows can't hold zero because of counter_count_check
+		sqlx::query(r#"DELETE FROM counter WHERE user_id = ?1 AND server_id = ?2 AND emote = ?3"#)
+			.bind(user_id)
+			.bind(server_id)
+			.bind(emote)
+			.execute(&mut *tx)
+			.await?;
+	} else {
+		sqlx::query(
+			r#"INSERT INTO counter (user_id, server_id, emote, count) VALUES (?1, ?2, ?3, ?4)
+			ON CONFLICT (user_id, server_id, emote) DO
+			UPDATE SET count = excluded.count"#,
+		)
+		.bind(user_id)
+		.bind(server_id)
+		.bind(emote)
+		.bind(count as i32)
+		.execute(&mut *tx)
+		.await?;
+	}
+	let change = Change {
+		action: "set_count",
+		user_id: Some(user_id.into()),
+		target: Some(format!("*{emote}*")),
+		old_value: Some(old_count.to_string()),
+		new_value: Some(count.to_string()),
+		reason: Some(reason),
+	};
+	insert_audit(&mut *tx, Some(actor_id), server_id, change).await?;
+	tx.commit().await?;
+
+	Ok(old_count as u32)
+}
+pub async fn reset_counts(
+	pool: &SqlitePool,
+	actor_id: i64,
+	server_id: i64,
+	user_id: Option<i64>,
+	reason: &str,
+) -> sqlx::Result<u64> {
+	let mut tx = pool.begin().await?;
+	// the events go too, so deleting an old message doesn't take from the new counts
+	sqlx::query(
+		r#"DELETE FROM count_events WHERE server_id = ?1 AND (user_id = ?2 OR ?2 IS NULL)"#,
+	)
+	.bind(server_id)
+	.bind(user_id)
+	.execute(&mut *tx)
+	.await?;
+	let removed: i64 = sqlx::query_scalar::<_, i64>(
+		r#"DELETE FROM counter WHERE server_id = ?1 AND (user_id = ?2 OR ?2 IS NULL)
+		RETURNING count"#,
+	)
+	.bind(server_id)
+	.bind(user_id)
+	.fetch_all(&mut *tx)
+	.await?
+	.into_iter()
+	.sum();
+	let change = Change {
+		action: if user_id.is_some() {
+			"reset_user"
+		} else {
+			"reset_server"
+		},
+		user_id: user_id.map(Into::into),
+		target: None,
+		old_value: Some(removed.to_string()),
+		new_value: Some(0.to_string()),
+		reason: Some(reason),
+	};
+	insert_audit(&mut *tx, Some(actor_id), server_id, change).await?;
+	tx.commit().await?;
+
+	Ok(removed as u64)
+}
+
+pub async fn user_counts(
+	pool: &SqlitePool,
+	user_id: i64,
+	server_id: Option<i64>,
+) -> sqlx::Result<Vec<UserCount>> {
+	Ok(sqlx::query_as::<_, (String, i64)>(concat!(
+		r#"SELECT emote, SUM(count) AS total
+		FROM counter
+		WHERE user_id = ?1 AND (server_id = ?2 OR ?2 IS NULL)
+		AND NOT "#,
+		opted_out!("counter"),
+		r#"
+		GROUP BY emote
+		ORDER BY total DESC"#
+	))
+	.bind(user_id)
+	.bind(server_id)
+	.fetch_all(pool)
+	.await?
+	.into_iter()
+	.map(|(emote, count)| UserCount {
+		emote: emote.into(),
+		count: count.into(),
+	})
+	.collect())
+}
+
+pub async fn set_opt_out(
+	pool: &SqlitePool,
+	user_id: i64,
+	server_id: Option<i64>,
+	value: bool,
+) -> sqlx::Result<Option<bool>> {
+	let mut tx = pool.begin().await?;
+	let old = match server_id {
+		Some(server_id) => {
+			let old = sqlx::query_scalar::<_, Option<bool>>(
+				r#"SELECT opt_out FROM user_server_options WHERE user_id = ?1 AND server_id = ?2"#,
+			)
+			.bind(user_id)
+			.bind(server_id)
+			.fetch_optional(&mut *tx)
+			.await?
+			.flatten();
+			sqlx::query(
+				r#"INSERT INTO user_server_options (user_id, server_id, opt_out) VALUES (?1, ?2, ?3)
+				ON CONFLICT (user_id, server_id) DO UPDATE
+				SET opt_out = excluded.opt_out"#,
+			)
+			.bind(user_id)
+			.bind(server_id)
+			.bind(value)
+			.execute(&mut *tx)
+			.await?;
+			old
+		}
+		None => {
+			let old = sqlx::query_scalar(r#"SELECT opt_out FROM options WHERE user_id = ?1"#)
+				.bind(user_id)
+				.fetch_optional(&mut *tx)
+				.await?;
+			sqlx::query(
+				r#"INSERT INTO options (user_id, opt_out) VALUES (?1, ?2)
+				ON CONFLICT (user_id) DO UPDATE
+				SET opt_out = excluded.opt_out"#,
+			)
+			.bind(user_id)
+			.bind(value)
+			.execute(&mut *tx)
+			.await?;
+			old
+		}
+	};
+	tx.commit().await?;
+	Ok(old)
+}
+pub async fn set_global(
+	pool: &SqlitePool,
+	user_id: i64,
+	username: Option<&str>,
+) -> sqlx::Result<()> {
+	sqlx::query(
+		r#"INSERT INTO options (user_id, global, username) VALUES (?1, ?2, ?3)
+		ON CONFLICT (user_id) DO UPDATE
+		SET global = excluded.global, username = excluded.username"#,
+	)
+	.bind(user_id)
+	.bind(username.is_some())
+	.bind(username)
+	.execute(pool)
+	.await
+	.map(|_| ())
+}
+pub async fn is_opt_out(
+	pool: &SqlitePool,
+	user_id: i64,
+	server_id: Option<i64>,
+) -> sqlx::Result<bool> {
+	sqlx::query_scalar(
+		r#"SELECT COALESCE(
+			(SELECT opt_out FROM user_server_options WHERE user_id = ?1 AND server_id = ?2),
+			(SELECT opt_out FROM options WHERE user_id = ?1),
+			false
+		)"#,
+	)
+	.bind(user_id)
+	.bind(server_id)
+	.fetch_one(pool)
+	.await
+}
+
+pub async fn mute_all(
+	pool: &SqlitePool,
+	server_id: i64,
+	value: Option<u32>,
+) -> sqlx::Result<Option<u32>> {
+	let mut tx = pool.begin().await?;
+	let old = sqlx::query_scalar::<_, Option<i32>>(
+		r#"SELECT mute_all FROM server_options WHERE server_id = ?1"#,
+	)
+	.bind(server_id)
+	.fetch_optional(&mut *tx)
+	.await?
+	.flatten();
+	sqlx::query(
+		r#"INSERT INTO server_options (server_id, mute_all) VALUES (?1, ?2)
+		ON CONFLICT (server_id) DO UPDATE
+		SET mute_all = excluded.mute_all"#,
+	)
+	.bind(server_id)
+	.bind(value.map(|v| v as i32))
+	.execute(&mut *tx)
+	.await?;
+	tx.commit().await?;
+	Ok(old.map(|v| v as u32))
+}
+pub async fn set_match_mode(
+	pool: &SqlitePool,
+	server_id: i64,
+	mode: MatchMode,
+) -> sqlx::Result<MatchMode> {
+	let mut tx = pool.begin().await?;
+	let old: Option<String> =
+		sqlx::query_scalar(r#"SELECT match_mode FROM server_options WHERE server_id = ?1"#)
+			.bind(server_id)
+			.fetch_optional(&mut *tx)
+			.await?;
+	sqlx::query(
+		r#"INSERT INTO server_options (server_id, match_mode) VALUES (?1, ?2)
+		ON CONFLICT (server_id) DO UPDATE
+		SET match_mode = excluded.match_mode"#,
+	)
+	.bind(server_id)
+	.bind(mode.as_str())
+	.execute(&mut *tx)
+	.await?;
+	tx.commit().await?;
+	Ok(old
+		.as_deref()
+		.and_then(MatchMode::from_choice)
+		.unwrap_or_default())
+}
+pub async fn match_mode(pool: &SqlitePool, server_id: i64) -> sqlx::Result<MatchMode> {
+	let mode: Option<String> =
+		sqlx::query_scalar(r#"SELECT match_mode FROM server_options WHERE server_id = ?1"#)
+			.bind(server_id)
+			.fetch_optional(pool)
+			.await?;
+	Ok(mode
+		.as_deref()
+		.and_then(MatchMode::from_choice)
+		.unwrap_or_default())
+}
+pub async fn set_language(
+	pool: &SqlitePool,
+	server_id: i64,
+	language: Language,
+) -> sqlx::Result<Language> {
+	let mut tx = pool.begin().await?;
+	let old: Option<String> =
+		sqlx::query_scalar(r#"SELECT language FROM server_options WHERE server_id = ?1"#)
+			.bind(server_id)
+			.fetch_optional(&mut *tx)
+			.await?;
+	sqlx::query(
+		r#"INSERT INTO server_options (server_id, language) VALUES (?1, ?2)
+		ON CONFLICT (server_id) DO UPDATE
+		SET language = excluded.language"#,
+	)
+	.bind(server_id)
+	.bind(language.as_str())
+	.execute(&mut *tx)
+	.await?;
+	tx.commit().await?;
+	Ok(old.map(Into::into).unwrap_or_default())
+}
+pub async fn language(pool: &SqlitePool, server_id: i64) -> sqlx::Result<Language> {
+	let language: Option<String> =
+		sqlx::query_scalar(r#"SELECT language FROM server_options WHERE server_id = ?1"#)
+			.bind(server_id)
+			.fetch_optional(pool)
+			.await?;
+	Ok(language.map(Into::into).unwrap_or_default())
+}
+pub async fn set_channel_mode(
+	pool: &SqlitePool,
+	server_id: i64,
+	channel_id: i64,
+	mode: Option<ChannelMode>,
+) -> sqlx::Result<Option<ChannelMode>> {
+	let old: Option<String> = match mode {
+		Some(mode) => {
+			let mut tx = pool.begin().await?;
+			let old = sqlx::query_scalar(
+				r#"SELECT mode FROM channel_options WHERE server_id = ?1 AND channel_id = ?2"#,
+			)
+			.bind(server_id)
+			.bind(channel_id)
+			.fetch_optional(&mut *tx)
+			.await?;
+			sqlx::query(
+				r#"INSERT INTO channel_options (server_id, channel_id, mode) VALUES (?1, ?2, ?3)
+				ON CONFLICT (server_id, channel_id) DO UPDATE
+				SET mode = excluded.mode"#,
+			)
+			.bind(server_id)
+			.bind(channel_id)
+			.bind(mode.as_str())
+			.execute(&mut *tx)
+			.await?;
+			tx.commit().await?;
+			old
+		}
+		None => {
+			sqlx::query_scalar(
+				r#"DELETE FROM channel_options WHERE server_id = ?1 AND channel_id = ?2
+				RETURNING mode"#,
+			)
+			.bind(server_id)
+			.bind(channel_id)
+			.fetch_optional(pool)
+			.await?
+		}
+	};
+	Ok(old.map(Into::into))
+}
+pub async fn channel_options(
+	pool: &SqlitePool,
+	server_id: i64,
+) -> sqlx::Result<Vec<ChannelOption>> {
+	Ok(sqlx::query_as::<_, (i64, String)>(
+		r#"SELECT channel_id, mode FROM channel_options
+		WHERE server_id = ?1
+		ORDER BY mode, channel_id"#,
+	)
+	.bind(server_id)
+	.fetch_all(pool)
+	.await?
+	.into_iter()
+	.map(|(channel_id, mode)| ChannelOption {
+		channel_id: channel_id.into(),
+		mode: mode.into(),
+	})
+	.collect())
+}
+pub async fn set_silent(
+	pool: &SqlitePool,
+	user_id: i64,
+	server_id: Option<i64>,
+	value: Option<u32>,
+) -> sqlx::Result<Option<Option<u32>>> {
+	let value = value.map(|v| v as i32);
+	let mut tx = pool.begin().await?;
+	let old = match server_id {
+		Some(server_id) => {
+			let old = sqlx::query_as::<_, (Option<i32>, bool)>(
+				r#"SELECT silent, silent_set FROM user_server_options
+				WHERE user_id = ?1 AND server_id = ?2"#,
+			)
+			.bind(user_id)
+			.bind(server_id)
+			.fetch_optional(&mut *tx)
+			.await?;
+			sqlx::query(
+				r#"INSERT INTO user_server_options (user_id, server_id, silent, silent_set) VALUES (?1, ?2, ?3, true)
+				ON CONFLICT (user_id, server_id) DO UPDATE
+				SET silent = excluded.silent, silent_set = true"#,
+			)
+			.bind(user_id)
+			.bind(server_id)
+			.bind(value)
+			.execute(&mut *tx)
+			.await?;
+			old.and_then(|(silent, set)| set.then_some(silent))
+		}
+		// the global option is always set, not being silent is the default
+		None => {
+			let old = sqlx::query_scalar::<_, Option<i32>>(
+				r#"SELECT silent FROM options WHERE user_id = ?1"#,
+			)
+			.bind(user_id)
+			.fetch_optional(&mut *tx)
+			.await?
+			.flatten();
+			sqlx::query(
+				r#"INSERT INTO options (user_id, silent) VALUES (?1, ?2)
+				ON CONFLICT (user_id) DO UPDATE
+				SET silent = excluded.silent"#,
+			)
+			.bind(user_id)
+			.bind(value)
+			.execute(&mut *tx)
+			.await?;
+			Some(old)
+		}
+	};
+	tx.commit().await?;
+	Ok(old.map(|old| old.map(|v| v as u32)))
+}
+pub async fn leaderboard(
+	pool: &SqlitePool,
+	server_id: Option<i64>,
+	emote: Option<&str>,
+	order: EmoteOrder,
+	period: Period,
+	offset: u32,
+	limit: u32,
+) -> sqlx::Result<LeaderboardPage> {
+	type Row = (String, i64, i32, i64, i64, Option<String>);
+	// one extra row tells whether there is a next page
+	let (offset, end) = (i64::from(offset), i64::from(offset + limit));
+	let rows = match emote {
+		Some(emote) => {
+			// `*` ranks every emote, no other emote is matched as a pattern
+			let emote = (emote != "*").then_some(emote);
+			sqlx::query_as::<_, Row>(concat!(
+				r#"WITH counts AS (
+					SELECT user_id, emote, count FROM counter
+					WHERE (server_id = ?1 OR ?1 IS NULL) AND ?5 IS NULL
+					AND NOT "#,
+				opted_out!("counter"),
+				r#"
+					UNION ALL
+					SELECT user_id, emote, 1 FROM count_events
+					WHERE (server_id = ?1 OR ?1 IS NULL) AND ?5 IS NOT NULL
+					AND NOT "#,
+				opted_out!("count_events"),
+				r#"
+					AND created_at >= "#,
+				period_start!("?5"),
+				r#"
+				), totals AS (
+					SELECT user_id, emote, SUM(count) AS count
+					FROM counts
+					WHERE ?1 IS NOT NULL
+					OR user_id IN (SELECT user_id FROM options WHERE global)
+					GROUP BY user_id, emote
+				), ranked AS (
+					SELECT user_id, emote, count,
+					SUM(count) OVER (PARTITION BY emote) AS total,
+					DENSE_RANK() OVER (PARTITION BY emote ORDER BY count DESC) AS rank,
+					ROW_NUMBER() OVER (PARTITION BY emote ORDER BY count DESC, user_id) AS position
+					FROM totals WHERE emote = ?2 OR ?2 IS NULL
+				)
+				SELECT
+					emote,
+					user_id,
+					count,
+					rank,
+					position,
+					CASE WHEN ?1 IS NULL THEN
+						(SELECT username FROM options WHERE options.user_id = ranked.user_id)
+					END AS username
+				FROM ranked
+				WHERE position > ?3 AND position <= ?4
+				ORDER BY CASE WHEN ?6 = 'total' THEN total END DESC, emote, position ASC"#
+			))
+			.bind(server_id)
+			.bind(emote)
+			.bind(offset)
+			.bind(end + 1)
+			.bind(period.trunc_field())
+			.bind(order.as_str())
+			.fetch_all(pool)
+			.await?
+		}
+		None => {
+			sqlx::query_as::<_, Row>(concat!(
+				r#"WITH counts AS (
+					SELECT user_id, count FROM counter
+					WHERE (server_id = ?1 OR ?1 IS NULL) AND ?4 IS NULL
+					AND NOT "#,
+				opted_out!("counter"),
+				r#"
+					UNION ALL
+					SELECT user_id, 1 FROM count_events
+					WHERE (server_id = ?1 OR ?1 IS NULL) AND ?4 IS NOT NULL
+					AND NOT "#,
+				opted_out!("count_events"),
+				r#"
+					AND created_at >= "#,
+				period_start!("?4"),
+				r#"
+				), totals AS (
+					SELECT user_id, SUM(count) AS count
+					FROM counts
+					WHERE ?1 IS NOT NULL
+					OR user_id IN (SELECT user_id FROM options WHERE global)
+					GROUP BY user_id
+				), ranked AS (
+					SELECT user_id, count,
+					DENSE_RANK() OVER (ORDER BY count DESC) AS rank,
+					ROW_NUMBER() OVER (ORDER BY count DESC, user_id) AS position
+					FROM totals
+				)
+				SELECT
+					'',
+					user_id,
+					count,
+					rank,
+					position,
+					CASE WHEN ?1 IS NULL THEN
+						(SELECT username FROM options WHERE options.user_id = ranked.user_id)
+					END AS username
+				FROM ranked
+				WHERE position > ?2 AND position <= ?3
+				ORDER BY position ASC"#
+			))
+			.bind(server_id)
+			.bind(offset)
+			.bind(end + 1)
+			.bind(period.trunc_field())
+			.fetch_all(pool)
+			.await?
+		}
+	};
+
+	let has_next = rows.iter().any(|row| row.4 > end);
+	let rows = rows
+		.into_iter()
+		.filter(|row| row.4 <= end)
+		.map(
+			|(emote, user_id, count, rank, position, username)| LeaderboardRow {
+				emote: emote.into(),
+				user_id: user_id.into(),
+				count: count.into(),
+				rank: rank.into(),
+				position: position.into(),
+				username,
+			},
+		)
+		.collect();
+	Ok(LeaderboardPage { rows, has_next })
+}
+
+pub async fn server_patterns(
+	pool: &SqlitePool,
+	server_id: i64,
+) -> sqlx::Result<Vec<ServerPattern>> {
+	Ok(sqlx::query_as::<_, (String, String, String)>(
+		r#"SELECT emote, pattern, kind FROM server_patterns
+		WHERE server_id = ?1
+		ORDER BY emote"#,
+	)
+	.bind(server_id)
+	.fetch_all(pool)
+	.await?
+	.into_iter()
+	.map(|(emote, pattern, kind)| ServerPattern {
+		emote: emote.into(),
+		pattern: pattern.into(),
+		kind: PatternKind::from(kind),
+	})
+	.collect())
+}
+pub async fn add_pattern(
+	pool: &SqlitePool,
+	server_id: i64,
+	pattern: &ServerPattern,
+) -> sqlx::Result<Option<String>> {
+	let mut tx = pool.begin().await?;
+	let old = sqlx::query_scalar(
+		r#"SELECT pattern FROM server_patterns WHERE server_id = ?1 AND emote = ?2"#,
+	)
+	.bind(server_id)
+	.bind(&*pattern.emote)
+	.fetch_optional(&mut *tx)
+	.await?;
+	sqlx::query(
+		r#"INSERT INTO server_patterns (server_id, emote, pattern, kind) VALUES (?1, ?2, ?3, ?4)
+		ON CONFLICT (server_id, emote) DO UPDATE
+		SET pattern = excluded.pattern, kind = excluded.kind"#,
+	)
+	.bind(server_id)
+	.bind(&*pattern.emote)
+	.bind(&*pattern.pattern)
+	.bind(pattern.kind.as_str())
+	.execute(&mut *tx)
+	.await?;
+	tx.commit().await?;
+	Ok(old)
+}
+pub async fn remove_pattern(
+	pool: &SqlitePool,
+	server_id: i64,
+	emote: &str,
+) -> sqlx::Result<Option<String>> {
+	sqlx::query_scalar(
+		r#"DELETE FROM server_patterns WHERE server_id = ?1 AND emote = ?2
+		RETURNING pattern"#,
+	)
+	.bind(server_id)
+	.bind(emote)
+	.fetch_optional(pool)
+	.await
+}
+
+pub async fn reply_templates(
+	pool: &SqlitePool,
+	server_id: i64,
+) -> sqlx::Result<Vec<ReplyTemplate>> {
+	Ok(sqlx::query_as::<_, (String, String)>(
+		r#"SELECT emote, template FROM reply_templates
+		WHERE server_id = ?1
+		ORDER BY emote"#,
+	)
+	.bind(server_id)
+	.fetch_all(pool)
+	.await?
+	.into_iter()
+	.map(|(emote, template)| ReplyTemplate {
+		emote: emote.into(),
+		template: template.into(),
+	})
+	.collect())
+}
+pub async fn set_reply_template(
+	pool: &SqlitePool,
+	server_id: i64,
+	emote: &str,
+	template: &str,
+) -> sqlx::Result<Option<String>> {
+	let mut tx = pool.begin().await?;
+	let old = sqlx::query_scalar(
+		r#"SELECT template FROM reply_templates WHERE server_id = ?1 AND emote = ?2"#,
+	)
+	.bind(server_id)
+	.bind(emote)
+	.fetch_optional(&mut *tx)
+	.await?;
+	sqlx::query(
+		r#"INSERT INTO reply_templates (server_id, emote, template) VALUES (?1, ?2, ?3)
+		ON CONFLICT (server_id, emote) DO UPDATE
+		SET template = excluded.template"#,
+	)
+	.bind(server_id)
+	.bind(emote)
+	.bind(template)
+	.execute(&mut *tx)
+	.await?;
+	tx.commit().await?;
+	Ok(old)
+}
+pub async fn remove_reply_template(
+	pool: &SqlitePool,
+	server_id: i64,
+	emote: &str,
+) -> sqlx::Result<Option<String>> {
+	sqlx::query_scalar(
+		r#"DELETE FROM reply_templates WHERE server_id = ?1 AND emote = ?2
+		RETURNING template"#,
+	)
+	.bind(server_id)
+	.bind(emote)
+	.fetch_optional(pool)
+	.await
+}
+pub async fn user_rank(
+	pool: &SqlitePool,
+	user_id: i64,
+	server_id: i64,
+	emote: &str,
+) -> sqlx::Result<Option<i64>> {
+	sqlx::query_scalar(concat!(
+		r#"SELECT rank FROM (
+			SELECT user_id, DENSE_RANK() OVER (ORDER BY count DESC) AS rank
+			FROM counter WHERE server_id = ?1 AND emote = ?2
+			AND NOT "#,
+		opted_out!("counter"),
+		r#"
+		) AS ranked
+		WHERE user_id = ?3"#
+	))
+	.bind(server_id)
+	.bind(emote)
+	.bind(user_id)
+	.fetch_optional(pool)
+	.await
+}
+
+pub async fn emote_aliases(pool: &SqlitePool, server_id: i64) -> sqlx::Result<Vec<EmoteAlias>> {
+	Ok(sqlx::query_as::<_, (String, String)>(
+		r#"SELECT alias, emote FROM emote_aliases
+		WHERE server_id = ?1
+		ORDER BY emote, alias"#,
+	)
+	.bind(server_id)
+	.fetch_all(pool)
+	.await?
+	.into_iter()
+	.map(|(alias, emote)| EmoteAlias {
+		alias: alias.into(),
+		emote: emote.into(),
+	})
+	.collect())
+}
+pub async fn add_alias(
+	pool: &SqlitePool,
+	server_id: i64,
+	alias: &str,
+	emote: &str,
+) -> sqlx::Result<Option<String>> {
+	let mut tx = pool.begin().await?;
+	let old = sqlx::query_scalar(
+		r#"SELECT emote FROM emote_aliases WHERE server_id = ?1 AND alias = ?2"#,
+	)
+	.bind(server_id)
+	.bind(alias)
+	.fetch_optional(&mut *tx)
+	.await?;
+	for query in [
+		r#"INSERT INTO emote_aliases (server_id, alias, emote) VALUES (?1, ?2, ?3)
+		ON CONFLICT (server_id, alias) DO UPDATE
+		SET emote = excluded.emote"#,
+		// aliases of the alias now point to the new emote
+		r#"UPDATE emote_aliases SET emote = ?3 WHERE server_id = ?1 AND emote = ?2"#,
+		r#"INSERT INTO counter (user_id, server_id, emote, count)
+		SELECT user_id, server_id, ?3, count FROM counter WHERE server_id = ?1 AND emote = ?2
+		ON CONFLICT (user_id, server_id, emote) DO UPDATE
+		SET count = counter.count + excluded.count"#,
+		r#"DELETE FROM counter WHERE server_id = ?1 AND emote = ?2"#,
+		r#"UPDATE count_events SET emote = ?3 WHERE server_id = ?1 AND emote = ?2"#,
+	] {
+		sqlx::query(query)
+			.bind(server_id)
+			.bind(alias)
+			.bind(emote)
+			.execute(&mut *tx)
+			.await?;
+	}
+	tx.commit().await?;
+	Ok(old)
+}
+pub async fn remove_alias(
+	pool: &SqlitePool,
+	server_id: i64,
+	alias: &str,
+) -> sqlx::Result<Option<String>> {
+	sqlx::query_scalar(
+		r#"DELETE FROM emote_aliases WHERE server_id = ?1 AND alias = ?2
+		RETURNING emote"#,
+	)
+	.bind(server_id)
+	.bind(alias)
+	.fetch_optional(pool)
+	.await
+}
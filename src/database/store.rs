@@ -0,0 +1,221 @@
+use std::sync::Arc;
+
+use serenity::{async_trait, model::Timestamp};
+
+use crate::{
+	locale::Language,
+	matcher::{Found, Matcher},
+	template::ReplyTemplate,
+};
+
+use super::{
+	Change, CountedMessage, DatabaseHandler, EmoteOrder, LeaderboardPage, MessageEvent, Period,
+	UserCount,
+};
+
+/// Where counts and user options are kept, everything counting a message and the
+/// user commands need. Server settings beyond these are only in the database
+#[async_trait]
+pub trait CounterStore: Send + Sync {
+	async fn matcher(&self, server_id: u64) -> sqlx::Result<Arc<Matcher>>;
+	/// `parent_id` is the channel a thread is in
+	async fn is_channel_counted(
+		&self,
+		server_id: u64,
+		channel_id: u64,
+		parent_id: Option<u64>,
+	) -> sqlx::Result<bool>;
+	async fn language(&self, server_id: u64) -> sqlx::Result<Language>;
+	async fn reply_templates(&self, server_id: u64) -> sqlx::Result<Vec<ReplyTemplate>>;
+
+	/// Counts every found emote, `None` if the user opted out
+	async fn count_message(
+		&self,
+		user_id: u64,
+		server_id: u64,
+		channel_id: u64,
+		message_id: u64,
+		found: &[Found<'_>],
+	) -> sqlx::Result<Option<CountedMessage>>;
+	/// Replaces the counts of an edited message with the found emotes, `None` if the user opted out.
+	/// Emotes it counted before keep the time they were counted at, new ones count from `sent_at`
+	async fn recount_message(
+		&self,
+		user_id: u64,
+		server_id: u64,
+		channel_id: u64,
+		message_id: u64,
+		sent_at: Timestamp,
+		found: &[Found<'_>],
+	) -> sqlx::Result<Option<CountedMessage>>;
+	async fn set_reply(&self, message_id: u64, reply_id: u64) -> sqlx::Result<()>;
+	async fn message_events(&self, message_id: u64) -> sqlx::Result<Vec<MessageEvent>>;
+	/// Rolls back the counts of deleted messages, returns how many events were removed
+	async fn remove_messages(&self, message_ids: &[u64]) -> sqlx::Result<u64>;
+
+	/// The user's counts in the server, or in every server without one
+	async fn user_counts(
+		&self,
+		user_id: u64,
+		server_id: Option<u64>,
+	) -> sqlx::Result<Vec<UserCount>>;
+	async fn user_rank(
+		&self,
+		user_id: u64,
+		server_id: u64,
+		emote: &str,
+	) -> sqlx::Result<Option<i64>>;
+	async fn leaderboard(
+		&self,
+		server_id: Option<u64>,
+		emote: Option<&str>,
+		order: EmoteOrder,
+		period: Period,
+		offset: u32,
+		limit: u32,
+	) -> sqlx::Result<LeaderboardPage>;
+
+	async fn set_opt_out(
+		&self,
+		user_id: u64,
+		server_id: Option<u64>,
+		value: bool,
+	) -> sqlx::Result<Option<bool>>;
+	async fn is_opt_out(&self, user_id: u64, server_id: Option<u64>) -> sqlx::Result<bool>;
+	async fn set_silent(
+		&self,
+		user_id: u64,
+		server_id: Option<u64>,
+		value: Option<u32>,
+	) -> sqlx::Result<Option<Option<u32>>>;
+	async fn set_global(&self, user_id: u64, username: Option<&str>) -> sqlx::Result<()>;
+	async fn mute_all(&self, server_id: u64, value: Option<u32>) -> sqlx::Result<Option<u32>>;
+
+	async fn audit(&self, actor_id: u64, server_id: u64, change: Change<'_>) -> sqlx::Result<()>;
+}
+
+#[async_trait]
+impl CounterStore for DatabaseHandler {
+	async fn matcher(&self, server_id: u64) -> sqlx::Result<Arc<Matcher>> {
+		DatabaseHandler::matcher(self, server_id).await
+	}
+	async fn is_channel_counted(
+		&self,
+		server_id: u64,
+		channel_id: u64,
+		parent_id: Option<u64>,
+	) -> sqlx::Result<bool> {
+		DatabaseHandler::is_channel_counted(self, server_id, channel_id, parent_id.map(Into::into))
+			.await
+	}
+	async fn language(&self, server_id: u64) -> sqlx::Result<Language> {
+		DatabaseHandler::language(self, server_id).await
+	}
+	async fn reply_templates(&self, server_id: u64) -> sqlx::Result<Vec<ReplyTemplate>> {
+		DatabaseHandler::reply_templates(self, server_id).await
+	}
+
+	async fn count_message(
+		&self,
+		user_id: u64,
+		server_id: u64,
+		channel_id: u64,
+		message_id: u64,
+		found: &[Found<'_>],
+	) -> sqlx::Result<Option<CountedMessage>> {
+		DatabaseHandler::count_message(self, user_id, server_id, channel_id, message_id, found)
+			.await
+	}
+	async fn recount_message(
+		&self,
+		user_id: u64,
+		server_id: u64,
+		channel_id: u64,
+		message_id: u64,
+		sent_at: Timestamp,
+		found: &[Found<'_>],
+	) -> sqlx::Result<Option<CountedMessage>> {
+		DatabaseHandler::recount_message(
+			self, user_id, server_id, channel_id, message_id, sent_at, found,
+		)
+		.await
+	}
+	async fn set_reply(&self, message_id: u64, reply_id: u64) -> sqlx::Result<()> {
+		DatabaseHandler::set_reply(self, message_id, reply_id).await
+	}
+	async fn message_events(&self, message_id: u64) -> sqlx::Result<Vec<MessageEvent>> {
+		DatabaseHandler::message_events(self, message_id).await
+	}
+	async fn remove_messages(&self, message_ids: &[u64]) -> sqlx::Result<u64> {
+		DatabaseHandler::remove_messages(self, message_ids.iter().copied()).await
+	}
+
+	async fn user_counts(
+		&self,
+		user_id: u64,
+		server_id: Option<u64>,
+	) -> sqlx::Result<Vec<UserCount>> {
+		match server_id {
+			Some(server_id) => self.get_user_server_counts(user_id, server_id).await,
+			None => self.get_user_counts(user_id).await,
+		}
+	}
+	async fn user_rank(
+		&self,
+		user_id: u64,
+		server_id: u64,
+		emote: &str,
+	) -> sqlx::Result<Option<i64>> {
+		DatabaseHandler::user_rank(self, user_id, server_id, emote).await
+	}
+	async fn leaderboard(
+		&self,
+		server_id: Option<u64>,
+		emote: Option<&str>,
+		order: EmoteOrder,
+		period: Period,
+		offset: u32,
+		limit: u32,
+	) -> sqlx::Result<LeaderboardPage> {
+		DatabaseHandler::leaderboard(
+			self,
+			server_id.map(Into::into),
+			emote,
+			order,
+			period,
+			offset,
+			limit,
+		)
+		.await
+	}
+
+	async fn set_opt_out(
+		&self,
+		user_id: u64,
+		server_id: Option<u64>,
+		value: bool,
+	) -> sqlx::Result<Option<bool>> {
+		DatabaseHandler::set_opt_out(self, user_id, server_id.map(Into::into), value).await
+	}
+	async fn is_opt_out(&self, user_id: u64, server_id: Option<u64>) -> sqlx::Result<bool> {
+		DatabaseHandler::is_opt_out(self, user_id, server_id.map(Into::into)).await
+	}
+	async fn set_silent(
+		&self,
+		user_id: u64,
+		server_id: Option<u64>,
+		value: Option<u32>,
+	) -> sqlx::Result<Option<Option<u32>>> {
+		DatabaseHandler::set_silent(self, user_id, server_id.map(Into::into), value).await
+	}
+	async fn set_global(&self, user_id: u64, username: Option<&str>) -> sqlx::Result<()> {
+		DatabaseHandler::set_global(self, user_id, username).await
+	}
+	async fn mute_all(&self, server_id: u64, value: Option<u32>) -> sqlx::Result<Option<u32>> {
+		DatabaseHandler::mute_all(self, server_id, value).await
+	}
+
+	async fn audit(&self, actor_id: u64, server_id: u64, change: Change<'_>) -> sqlx::Result<()> {
+		DatabaseHandler::audit(self, actor_id, server_id, change).await
+	}
+}
@@ -1,5 +1,7 @@
 use std::fmt::Display;
 
+use serde::{Serialize, Serializer};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 #[repr(transparent)]
 pub struct PsqlU<N>(pub N);
@@ -31,6 +33,13 @@ impl Display for PsqlU<u64> {
 	}
 }
 
+/// Serialized as a string like Discord's snowflakes, which don't fit in a JavaScript number
+impl Serialize for PsqlU<u64> {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_str(self)
+	}
+}
+
 pub type PsqlU32 = PsqlU<u32>;
 impl From<u32> for PsqlU<u32> {
 	fn from(value: u32) -> Self {
@@ -0,0 +1,256 @@
+pub fn text(english: &str) -> Option<&'static str> {
+	Some(match english {
+		// errors
+		"You can only run this command in a server (this should not be possible)" => "Diesen Befehl kannst du nur in einem Server benutzen (das sollte nicht möglich sein)",
+		"Incorrect argument type provided (this should not be possible)" => "Falscher Argumenttyp angegeben (das sollte nicht möglich sein)",
+		"You can only change a setting for this server in a server" => "Einstellungen für einen Server kannst du nur in einem Server ändern",
+		"This command needs the database, which I'm running without right now" => "Dieser Befehl braucht die Datenbank, ohne die ich gerade laufe",
+
+		// user settings
+		"I will count your ':3's in this server now UwU" => "Ich zähle deine ':3's in diesem Server jetzt UwU",
+		"I will count your ':3's now UwU" => "Ich zähle deine ':3's jetzt UwU",
+		"I won't count your ':3's in this server now qwq" => "Ich zähle deine ':3's in diesem Server nicht mehr qwq",
+		"I won't count your ':3's now qwq" => "Ich zähle deine ':3's nicht mehr qwq",
+		"You will appear on the global leaderboard as {username} (run this again if you change your name)" => "Du erscheinst auf der globalen Rangliste als {username} (benutze den Befehl erneut, wenn du deinen Namen änderst)",
+		"You won't appear on the global leaderboard anymore" => "Du erscheinst nicht mehr auf der globalen Rangliste",
+		"I won't respond to your messages in this server but will still count x3s" => "Ich antworte in diesem Server nicht mehr auf deine Nachrichten, zähle aber weiter x3s",
+		"I won't respond to your messages but will still count x3s" => "Ich antworte nicht mehr auf deine Nachrichten, zähle aber weiter x3s",
+		"I will only respond to every {nth} x3 in this server" => "Ich antworte in diesem Server nur noch auf jedes {nth} x3",
+		"I will only respond to every {nth} x3" => "Ich antworte nur noch auf jedes {nth} x3",
+		"I will now respond to your messages in this server" => "Ich antworte jetzt in diesem Server auf deine Nachrichten",
+		"I will now respond to your messages" => "Ich antworte jetzt auf deine Nachrichten",
+
+		// counts and leaderboards
+		"You don't have any x3s yet :c" => "Du hast noch keine x3s :c",
+		"This user doesn't have any x3s yet :c" => "Diese Person hat noch keine x3s :c",
+		"You opted out, so I don't count your x3s :c" => "Du hast dich abgemeldet, deshalb zähle ich deine x3s nicht :c",
+		"<@{user}> opted out, so I don't count their x3s :c" => "<@{user}> hat sich abgemeldet, deshalb zähle ich ihre x3s nicht :c",
+		"Here are your counts:\n{counts}" => "Hier sind deine Zählungen:\n{counts}",
+		"Here are <@{user}>'s counts:\n{counts}" => "Hier sind die Zählungen von <@{user}>:\n{counts}",
+		"Leaderboard" => "Rangliste",
+		"Leaderboard (today)" => "Rangliste (heute)",
+		"Leaderboard (this week)" => "Rangliste (diese Woche)",
+		"Leaderboard (this month)" => "Rangliste (diesen Monat)",
+		"Global leaderboard" => "Globale Rangliste",
+		"Global leaderboard (today)" => "Globale Rangliste (heute)",
+		"Global leaderboard (this week)" => "Globale Rangliste (diese Woche)",
+		"Global leaderboard (this month)" => "Globale Rangliste (diesen Monat)",
+		"Page {page}" => "Seite {page}",
+		"Nobody has any x3s here yet :c" => "Hier hat noch niemand x3s :c",
+		"Nobody has any x3s here today yet :c" => "Hier hat heute noch niemand x3s :c",
+		"Nobody has any x3s here this week yet :c" => "Hier hat diese Woche noch niemand x3s :c",
+		"Nobody has any x3s here this month yet :c" => "Hier hat diesen Monat noch niemand x3s :c",
+		"All emotes" => "Alle Emotes",
+		"Previous" => "Zurück",
+		"Next" => "Weiter",
+
+		// server settings
+		"I won't respond to messages in this server but will still count x3s" => "Ich antworte in diesem Server nicht mehr auf Nachrichten, zähle aber weiter x3s",
+		"I will now respond to messages in this server" => "Ich antworte jetzt in diesem Server auf Nachrichten",
+		"This server already has {max} custom emotes" => "Dieser Server hat schon {max} eigene Emotes",
+		"The emote name must be between 1 and {max} characters long" => "Der Name des Emotes muss zwischen 1 und {max} Zeichen lang sein",
+		"The pattern must be at most {max} characters long" => "Das Muster darf höchstens {max} Zeichen lang sein",
+		"This pattern is invalid:\n```\n{why}\n```" => "Dieses Muster ist ungültig:\n```\n{why}\n```",
+		"I will now count `{pattern}` as *{emote}*" => "Ich zähle `{pattern}` jetzt als *{emote}*",
+		"This is not a single custom or unicode emoji" => "Das ist kein einzelnes eigenes oder Unicode-Emoji",
+		"I will now count {emoji}" => "Ich zähle jetzt {emoji}",
+		"I won't count *{emote}* in this server anymore" => "Ich zähle *{emote}* in diesem Server nicht mehr",
+		"*{emote}* is not a custom emote in this server" => "*{emote}* ist kein eigenes Emote in diesem Server",
+		"This server doesn't have any custom emotes" => "Dieser Server hat keine eigenen Emotes",
+		"emoji" => "Emoji",
+		"Here are the custom emotes in this server:\n{patterns}" => "Hier sind die eigenen Emotes in diesem Server:\n{patterns}",
+		"I will only count the emote a message ends with" => "Ich zähle nur das Emote, mit dem eine Nachricht endet",
+		"I will count the first emote anywhere in a message" => "Ich zähle das erste Emote irgendwo in einer Nachricht",
+		"I will count every emote in a message" => "Ich zähle jedes Emote in einer Nachricht",
+		"Emotes must be between 1 and {max} characters long" => "Emotes müssen zwischen 1 und {max} Zeichen lang sein",
+		"*{variant}* already counts as *{emote}*" => "*{variant}* zählt schon als *{emote}*",
+		"This server already has {max} aliases" => "Dieser Server hat schon {max} Aliase",
+		"*{variant}* now counts as *{emote}*, existing counts were merged" => "*{variant}* zählt jetzt als *{emote}*, bisherige Zählungen wurden zusammengeführt",
+		"*{variant}* counts as its own emote again, merged counts stay merged" => "*{variant}* zählt wieder als eigenes Emote, zusammengeführte Zählungen bleiben zusammengeführt",
+		"*{variant}* is not an alias in this server" => "*{variant}* ist kein Alias in diesem Server",
+		"This server doesn't have any emote aliases" => "Dieser Server hat keine Emote-Aliase",
+		"Here are the emote aliases in this server:\n{aliases}" => "Hier sind die Emote-Aliase in diesem Server:\n{aliases}",
+		"I won't count x3s in <#{channel}> and its threads anymore" => "Ich zähle keine x3s mehr in <#{channel}> und seinen Threads",
+		"I will count x3s in <#{channel}> and its threads, but only in allowed channels" => "Ich zähle x3s in <#{channel}> und seinen Threads, aber nur in erlaubten Kanälen",
+		"<#{channel}> is neither allowed nor blocked now" => "<#{channel}> ist jetzt weder erlaubt noch gesperrt",
+		"I count x3s in every channel" => "Ich zähle x3s in jedem Kanal",
+		"I count x3s in every channel except {blocked}" => "Ich zähle x3s in jedem Kanal außer {blocked}",
+		"I only count x3s in {allowed}" => "Ich zähle x3s nur in {allowed}",
+		"I only count x3s in {allowed}, and never in {blocked}" => "Ich zähle x3s nur in {allowed} und nie in {blocked}",
+		"That template is invalid: {why}" => "Diese Vorlage ist ungültig: {why}",
+		"I will reply to *{emote}* with:\n{template}" => "Ich antworte auf *{emote}* mit:\n{template}",
+		"I will reply to every emote with:\n{template}" => "Ich antworte auf jedes Emote mit:\n{template}",
+		"I will reply to *{emote}* with the default reply again" => "Ich antworte auf *{emote}* wieder mit der Standardantwort",
+		"I will reply to every emote with the default reply again" => "Ich antworte auf jedes Emote wieder mit der Standardantwort",
+		"There is no reply template for *{emote}*" => "Es gibt keine Antwortvorlage für *{emote}*",
+		"There is no reply template for all emotes" => "Es gibt keine Antwortvorlage für alle Emotes",
+		"This server uses the default reply" => "Dieser Server benutzt die Standardantwort",
+		"Every emote" => "Jedes Emote",
+		"Here are the reply templates in this server:\n{templates}" => "Hier sind die Antwortvorlagen in diesem Server:\n{templates}",
+		"I will reply to counted x3s in English in this server" => "Ich antworte in diesem Server auf Deutsch auf gezählte x3s",
+		"The reason must be between 1 and {max} characters long" => "Der Grund muss zwischen 1 und {max} Zeichen lang sein",
+		"*{emote}* isn't counted in this server" => "*{emote}* wird in diesem Server nicht gezählt",
+		"Set <@{user}>'s *{emote}* count from {old} to {count}" => "Die Zählung von *{emote}* für <@{user}> wurde von {old} auf {count} gesetzt",
+		"Reset <@{user}>'s counts in this server, {count} x3s were removed" => "Die Zählungen von <@{user}> in diesem Server wurden zurückgesetzt, {count} x3s wurden entfernt",
+		"This removes every count in this server and their history, which can't be undone" => "Das entfernt jede Zählung in diesem Server und ihren Verlauf, was nicht rückgängig gemacht werden kann",
+		"Reset" => "Zurücksetzen",
+		"Every count in this server was reset, {count} x3s were removed" => "Jede Zählung in diesem Server wurde zurückgesetzt, {count} x3s wurden entfernt",
+		"Nothing was changed in this server yet" => "In diesem Server wurde noch nichts geändert",
+		"Audit log" => "Änderungsprotokoll",
+		"nothing" => "nichts",
+		"A deleted user" => "Ein gelöschter Nutzer",
+
+		// personal data
+		"Here is everything I store about you" => "Hier ist alles, was ich über dich speichere",
+		"I couldn't export your data, please try again later" => "Ich konnte deine Daten nicht exportieren, bitte versuche es später noch einmal",
+		"Delete" => "Löschen",
+		"This deletes your counts, their history and your settings in every server, which can't be undone. You stay opted out afterwards, run /opt_in if you want to be counted again" => "Das löscht deine Zählungen, ihren Verlauf und deine Einstellungen in jedem Server, was nicht rückgängig gemacht werden kann. Danach bleibst du abgemeldet, benutze /opt_in, wenn du wieder gezählt werden willst",
+		"Only the owner of the bot can delete someone's data everywhere" => "Nur der Besitzer des Bots kann die Daten einer Person überall löschen",
+		"This deletes <@{user}>'s counts, their history and their settings in every server, which can't be undone" => "Das löscht die Zählungen von <@{user}>, ihren Verlauf und die Einstellungen in jedem Server, was nicht rückgängig gemacht werden kann",
+		"This deletes <@{user}>'s counts, their history and their settings in this server, which can't be undone" => "Das löscht die Zählungen von <@{user}>, ihren Verlauf und die Einstellungen in diesem Server, was nicht rückgängig gemacht werden kann",
+		"Everything I stored about you was deleted" => "Alles, was ich über dich gespeichert hatte, wurde gelöscht",
+		"Everything I stored about <@{user}> in this server was deleted" => "Alles, was ich in diesem Server über <@{user}> gespeichert hatte, wurde gelöscht",
+		"Everything I stored about <@{user}> was deleted" => "Alles, was ich über <@{user}> gespeichert hatte, wurde gelöscht",
+
+		// replies and templates
+		"*{emote}* **{count}** times" => "*{emote}* **{count}**-mal",
+		"You have ended your message with {counts}!" => "Du hast deine Nachricht mit {counts} beendet!",
+		"You have sent {counts}!" => "Du hast {counts} gesendet!",
+		"the template is empty" => "die Vorlage ist leer",
+		"the template is longer than {max} characters" => "die Vorlage ist länger als {max} Zeichen",
+		"a `{` is never closed" => "eine `{` wird nie geschlossen",
+		"`{{name}}` is not a placeholder, use {user}, {emote}, {count}, {ordinal} or {rank}" => "`{{name}}` ist kein Platzhalter, benutze {user}, {emote}, {count}, {ordinal} oder {rank}",
+		"unranked" => "ohne Platz",
+
+		// command descriptions
+		"Start tracking x3s" => "x3s wieder zählen",
+		"Stop tracking x3s" => "Keine x3s mehr zählen",
+		"Appear on the global leaderboard with your username" => "Mit deinem Namen auf der globalen Rangliste erscheinen",
+		"Stop appearing on the global leaderboard" => "Nicht mehr auf der globalen Rangliste erscheinen",
+		"Track x3s silently (don't send messages)" => "x3s still zählen (keine Nachrichten senden)",
+		"Track x3s verbosely (do send messages)" => "x3s gesprächig zählen (Nachrichten senden)",
+		"Get your x3 counts" => "Deine x3-Zählungen anzeigen",
+		"Get the x3 leaderboard for this server" => "Die x3-Rangliste dieses Servers anzeigen",
+		"Get the x3 leaderboard across every server" => "Die x3-Rangliste über alle Server anzeigen",
+		"Mute all count messages in the server" => "Alle Zählnachrichten im Server stummschalten",
+		"Unmute all count messages in the server" => "Die Stummschaltung der Zählnachrichten im Server aufheben",
+		"Manage the emotes counted in this server" => "Die in diesem Server gezählten Emotes verwalten",
+		"Choose which emotes in a message are counted in the server" => "Auswählen, welche Emotes einer Nachricht im Server gezählt werden",
+		"Manage which emotes count as the same one in this server" => "Verwalten, welche Emotes in diesem Server als dasselbe zählen",
+		"Manage which channels x3s are counted in" => "Verwalten, in welchen Kanälen x3s gezählt werden",
+		"Customise the reply to counted x3s" => "Die Antwort auf gezählte x3s anpassen",
+		"Choose the language of the replies to counted x3s in the server" => "Die Sprache der Antworten auf gezählte x3s im Server auswählen",
+		"Get everything I store about you" => "Alles abrufen, was ich über dich speichere",
+		"Delete everything I store about you" => "Alles löschen, was ich über dich speichere",
+		"Delete everything I store about a user who asked for it" => "Alles löschen, was ich über eine Person speichere, die darum gebeten hat",
+		"Fix the counts in this server" => "Die Zählungen in diesem Server korrigieren",
+		"List the latest changes to the settings and counts in this server" => "Die letzten Änderungen an den Einstellungen und Zählungen in diesem Server auflisten",
+
+		// option descriptions
+		"Send counts every nth message" => "Zählungen bei jeder n-ten Nachricht senden",
+		"Change the setting only in this server, or everywhere (the default)" => "Die Einstellung nur in diesem Server oder überall (Standard) ändern",
+		"Users per emote on each page" => "Personen pro Emote auf jeder Seite",
+		"Which emote leaderboard to display, all emotes combined if not set" => "Welche Emote-Rangliste angezeigt wird, ohne Angabe alle Emotes zusammen",
+		"How to order the emotes when displaying every emote" => "Wie die Emotes sortiert werden, wenn alle angezeigt werden",
+		"Which period to count x3s in" => "In welchem Zeitraum x3s gezählt werden",
+		"The user whose counts to display" => "Die Person, deren Zählungen angezeigt werden",
+		"The emote name to count matches as" => "Der Name des Emotes, als das Treffer gezählt werden",
+		"Regex the message has to end with" => "Regex, mit der die Nachricht enden muss",
+		"Count a new emote in this server" => "Ein neues Emote in diesem Server zählen",
+		"The custom or unicode emoji to count" => "Das eigene oder Unicode-Emoji, das gezählt wird",
+		"Count an emoji in this server" => "Ein Emoji in diesem Server zählen",
+		"Stop counting an emote in this server" => "Ein Emote in diesem Server nicht mehr zählen",
+		"List the emotes counted in this server" => "Die in diesem Server gezählten Emotes auflisten",
+		"The emote to count as another one" => "Das Emote, das als ein anderes zählt",
+		"The emote to count the variant as" => "Das Emote, als das die Variante zählt",
+		"Count an emote as another one in this server" => "Ein Emote in diesem Server als ein anderes zählen",
+		"Count a variant as its own emote again" => "Eine Variante wieder als eigenes Emote zählen",
+		"List the emote aliases in this server" => "Die Emote-Aliase in diesem Server auflisten",
+		"The channel to configure" => "Der Kanal, der eingestellt wird",
+		"Never count x3s in a channel" => "In einem Kanal nie x3s zählen",
+		"Only count x3s in allowed channels, starting with this one" => "Nur in erlaubten Kanälen x3s zählen, beginnend mit diesem",
+		"Remove a channel from the allowed or blocked channels" => "Einen Kanal aus den erlaubten oder gesperrten Kanälen entfernen",
+		"List the allowed and blocked channels" => "Die erlaubten und gesperrten Kanäle auflisten",
+		"The reply, can use {user}, {emote}, {count}, {ordinal} and {rank}" => "Die Antwort, kann {user}, {emote}, {count}, {ordinal} und {rank} benutzen",
+		"Only use the template for this emote" => "Die Vorlage nur für dieses Emote benutzen",
+		"Change the reply to counted x3s in this server" => "Die Antwort auf gezählte x3s in diesem Server ändern",
+		"Go back to the default reply" => "Zur Standardantwort zurückkehren",
+		"List the reply templates in this server" => "Die Antwortvorlagen in diesem Server auflisten",
+		"The template to preview, the saved one if not set" => "Die Vorlage für die Vorschau, ohne Angabe die gespeicherte",
+		"Show what a reply would look like" => "Zeigen, wie eine Antwort aussehen würde",
+		"Which emotes in a message to count" => "Welche Emotes einer Nachricht gezählt werden",
+		"The language to reply to counted x3s in" => "Die Sprache, in der auf gezählte x3s geantwortet wird",
+		"The user whose data to delete" => "Die Person, deren Daten gelöscht werden",
+		"Delete the data only in this server (the default), or everywhere (bot owner only)" => "Die Daten nur in diesem Server (Standard) oder überall (nur Besitzer des Bots) löschen",
+		"The user whose counts to change" => "Die Person, deren Zählungen geändert werden",
+		"The emote whose count to set" => "Das Emote, dessen Zählung gesetzt wird",
+		"The new count, 0 removes it" => "Die neue Zählung, 0 entfernt sie",
+		"Why the counts are changed, kept with the change" => "Warum die Zählungen geändert werden, wird mit der Änderung gespeichert",
+		"Set a user's count of an emote in this server" => "Die Zählung eines Emotes einer Person in diesem Server setzen",
+		"Remove a user's counts in this server" => "Die Zählungen einer Person in diesem Server entfernen",
+		"Remove every count in this server" => "Jede Zählung in diesem Server entfernen",
+		_ => return None,
+	})
+}
+
+/// Names of commands, options and choices, which Discord requires to be lowercase
+pub fn name(english: &str) -> Option<&'static str> {
+	Some(match english {
+		// commands
+		"opt_in" => "einwilligen",
+		"opt_out" => "abmelden",
+		"global_opt_in" => "global_einwilligen",
+		"global_opt_out" => "global_abmelden",
+		"silent" => "still",
+		"verbose" => "gesprächig",
+		"counts" => "zählungen",
+		"leaderboard" => "rangliste",
+		"global_leaderboard" => "globale_rangliste",
+		"mute_all" => "alle_stummschalten",
+		"unmute_all" => "stummschaltung_aufheben",
+		"pattern" => "muster",
+		"match_mode" => "zählmodus",
+		"channel" => "kanal",
+		"reply_template" => "antwortvorlage",
+		"language" => "sprache",
+		"export_my_data" => "meine_daten_exportieren",
+		"delete_my_data" => "meine_daten_löschen",
+		"delete_user_data" => "nutzerdaten_löschen",
+		"audit" => "protokoll",
+
+		// subcommands and options
+		"add" => "hinzufügen",
+		"remove" => "entfernen",
+		"list" => "liste",
+		"block" => "sperren",
+		"allow" => "erlauben",
+		"reset" => "zurücksetzen",
+		"set" => "festlegen",
+		"preview" => "vorschau",
+		"send_on" => "antworten_bei",
+		"scope" => "bereich",
+		"count" => "anzahl",
+		"order" => "reihenfolge",
+		"period" => "zeitraum",
+		"user" => "person",
+		"variant" => "variante",
+		"mode" => "modus",
+		"template" => "vorlage",
+		"reason" => "grund",
+		"set_count" => "zählung_setzen",
+		"reset_user" => "person_zurücksetzen",
+		"reset_server" => "server_zurücksetzen",
+
+		// choices
+		"today" => "heute",
+		"this week" => "diese Woche",
+		"this month" => "diesen Monat",
+		"all time" => "insgesamt",
+		"alphabetical" => "alphabetisch",
+		"most counted" => "am meisten gezählt",
+		"end_only" => "nur_am_ende",
+		"anywhere" => "überall",
+		"all_occurrences" => "alle_vorkommen",
+		_ => return None,
+	})
+}
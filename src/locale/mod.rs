@@ -0,0 +1,168 @@
+mod de;
+
+use std::{collections::HashMap, fmt::Display};
+
+/// A language the bot can respond in, the English text in the code is looked up in the others
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+	#[default]
+	English,
+	German,
+}
+
+impl Language {
+	/// Every language other than English, which needs no translation
+	pub const TRANSLATED: [Self; 1] = [Self::German];
+
+	/// Picks the language for a Discord locale like `en-US` or `de`, English if it has no translation
+	pub fn from_locale(locale: &str) -> Self {
+		match locale.split('-').next() {
+			Some("de") => Self::German,
+			_ => Self::English,
+		}
+	}
+
+	/// The Discord locale the language's command names and descriptions are registered for
+	pub fn locale(self) -> &'static str {
+		match self {
+			Self::English => "en-US",
+			Self::German => "de",
+		}
+	}
+
+	pub fn from_choice(choice: &str) -> Option<Self> {
+		match choice {
+			"english" => Some(Self::English),
+			"deutsch" => Some(Self::German),
+			_ => None,
+		}
+	}
+
+	pub fn as_str(self) -> &'static str {
+		match self {
+			Self::English => "en",
+			Self::German => "de",
+		}
+	}
+
+	/// Translates a response or description, falls back to the English text
+	pub fn text(self, english: &'static str) -> &'static str {
+		match self {
+			Self::English => english,
+			Self::German => de::text(english).unwrap_or(english),
+		}
+	}
+
+	/// Translates the name of a command, option or choice
+	pub fn name(self, english: &str) -> Option<&'static str> {
+		match self {
+			Self::English => None,
+			Self::German => de::name(english),
+		}
+	}
+
+	/// `1st`, `2nd`, `11th` and so on
+	pub fn ordinal(self, n: i64) -> String {
+		match self {
+			Self::English => {
+				let suffix = match (n % 10, n % 100) {
+					(_, 11..=13) => "th",
+					(1, _) => "st",
+					(2, _) => "nd",
+					(3, _) => "rd",
+					_ => "th",
+				};
+				format!("{n}{suffix}")
+			}
+			Self::German => format!("{n}."),
+		}
+	}
+}
+
+impl From<String> for Language {
+	fn from(value: String) -> Self {
+		match value.as_str() {
+			"de" => Self::German,
+			_ => Self::English,
+		}
+	}
+}
+
+/// The translated names for a command, option or choice, keyed by Discord locale
+pub fn name_localizations(english: &str) -> HashMap<String, String> {
+	Language::TRANSLATED
+		.into_iter()
+		.filter_map(|lang| Some((lang.locale().to_owned(), lang.name(english)?.to_owned())))
+		.collect()
+}
+
+/// The translated descriptions for a command or option, keyed by Discord locale
+pub fn description_localizations(english: &'static str) -> HashMap<String, String> {
+	Language::TRANSLATED
+		.into_iter()
+		.map(|lang| (lang, lang.text(english)))
+		.filter(|(_, text)| *text != english)
+		.map(|(lang, text)| (lang.locale().to_owned(), text.to_owned()))
+		.collect()
+}
+
+/// Replaces every `{name}` in the text with its value
+pub fn fill(text: &str, values: &[(&str, &dyn Display)]) -> String {
+	let mut filled = String::with_capacity(text.len());
+	let mut rest = text;
+	while let Some(start) = rest.find('{') {
+		filled.push_str(&rest[..start]);
+		let value = rest[start..].find('}').and_then(|len| {
+			let name = &rest[start + 1..start + len];
+			let (_, value) = values.iter().find(|(n, _)| *n == name)?;
+			Some((len, value))
+		});
+		match value {
+			Some((len, value)) => {
+				filled.push_str(&value.to_string());
+				rest = &rest[start + len + 1..];
+			}
+			None => {
+				filled.push('{');
+				rest = &rest[start + 1..];
+			}
+		}
+	}
+	filled.push_str(rest);
+	filled
+}
+
+/// Translates the text and fills in its `{name}`s
+#[macro_export]
+macro_rules! tr {
+	($lang:expr, $text:literal) => {
+		$lang.text($text).to_owned()
+	};
+	($lang:expr, $text:literal, $($name:ident = $value:expr), + $(,)?) => {
+		$crate::locale::fill(
+			$lang.text($text),
+			&[$((stringify!($name), &$value as &dyn std::fmt::Display)), +],
+		)
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn ordinals() {
+		let english: Vec<_> = [1, 2, 3, 4, 11, 12, 13, 21, 22, 23, 101, 111, 112]
+			.into_iter()
+			.map(|n| Language::English.ordinal(n))
+			.collect();
+		assert_eq!(
+			english,
+			[
+				"1st", "2nd", "3rd", "4th", "11th", "12th", "13th", "21st", "22nd", "23rd",
+				"101st", "111th", "112th"
+			]
+		);
+		assert_eq!(Language::German.ordinal(3), "3.");
+	}
+}
@@ -1,36 +1,93 @@
 mod command;
 mod database;
-
-#[macro_use]
-extern crate sqlx;
+mod locale;
+mod matcher;
+mod template;
 
 use std::collections::HashMap;
 use std::env;
+use std::future::Future;
+use std::process;
+use std::slice;
+use std::sync::Arc;
+use std::time::Duration;
 
 use command::{all::*, IntoCommand};
-use database::{DatabaseHandler, VerboseLevel};
+use database::{CounterStore, DatabaseHandler, MemoryStore, NewCount, VerboseLevel};
 use dotenvy::dotenv;
-use regex::Regex;
-use serenity::all::{Command, CommandInteraction, CreateInteractionResponse, Interaction};
+use locale::Language;
+use matcher::MatchMode;
+use serenity::all::{
+	Channel, ChannelId, Command, CommandInteraction, ComponentInteraction, CreateAllowedMentions,
+	CreateInteractionResponse, CreateMessage, EditMessage, Guild, GuildId, Interaction, MessageId,
+	MessageUpdateEvent, UnavailableGuild,
+};
 use serenity::async_trait;
+use serenity::cache::Settings as CacheSettings;
 use serenity::model::channel::Message;
 use serenity::model::gateway::Ready;
 use serenity::prelude::*;
-use sqlx::Pool;
+use template::{TemplateValues, ANY_EMOTE};
+use tokio::sync::OwnedMutexGuard;
+
+/// Messages cached per channel, edits compare against them to see what changed
+const MAX_CACHED_MESSAGES: usize = 100;
 
 struct Handler {
-	regex: Regex,
-	regex_captures: usize,
-	db_handler: DatabaseHandler,
+	/// The same handler as the database when there is one
+	store: Arc<dyn CounterStore>,
+	db_handler: Option<Arc<DatabaseHandler>>,
+	/// The messages being counted, by id
+	counting: std::sync::Mutex<HashMap<u64, Arc<Mutex<()>>>>,
+}
+
+/// The message `Handler::count` counts
+#[derive(Clone, Copy)]
+enum Counting {
+	New(u64),
+	/// An edited message, its earlier counts are replaced
+	Edit(MessageId),
+}
+
+/// Held while a message is counted, so an edit or delete can't race the count
+struct MessageLock<'a> {
+	counting: &'a std::sync::Mutex<HashMap<u64, Arc<Mutex<()>>>>,
+	message_id: u64,
+	_guard: OwnedMutexGuard<()>,
+}
+impl Drop for MessageLock<'_> {
+	fn drop(&mut self) {
+		let mut counting = self.counting.lock().unwrap();
+		// the map and this guard hold the only references once nobody else waits
+		if counting
+			.get(&self.message_id)
+			.is_some_and(|lock| Arc::strong_count(lock) == 2)
+		{
+			counting.remove(&self.message_id);
+		}
+	}
+}
+
+/// Runs a command with the store, or with the database if the bot has one
+macro_rules! run_with {
+	(store, $handler:expr, $exec:ident, $cmd:expr) => {
+		$exec(&*$handler.store, $cmd).await?
+	};
+	(database, $handler:expr, $exec:ident, $cmd:expr) => {
+		match &$handler.db_handler {
+			Some(db) => $exec(db, $cmd).await?,
+			None => database_required($cmd),
+		}
+	};
 }
 
 macro_rules! add_commands {
-	($($cmd:ident => $exec:ident), * $(,)?) => {
+	($($uses:ident $cmd:ident => $exec:ident), * $(,)?) => {
 		impl Handler {
 			async fn register_commands(&self, ctx: &Context) -> Result<(), SerenityError> {
 				println!("Registering commands...");
 
-				let old_commands = Command::get_global_commands(&ctx).await.unwrap_or_default();
+				let old_commands = Command::get_global_commands_with_localizations(&ctx).await.unwrap_or_default();
 				let mut old_commands: HashMap<_, _> = old_commands
 					.into_iter()
 					.map(|cmd| (cmd.name.to_owned(), cmd))
@@ -103,28 +160,233 @@ macro_rules! add_commands {
 			) -> sqlx::Result<Option<CreateInteractionResponse>> {
 				let msg = match cmd.data.name.as_str() {
 					$(
-						$cmd::NAME => $exec(&self.db_handler, cmd).await?,
+						$cmd::NAME => run_with!($uses, self, $exec, cmd),
 					)*
 					_ => return Ok(None),
 				};
 
 				Ok(Some(CreateInteractionResponse::Message(msg)))
 			}
+
+			async fn run_autocomplete(
+				&self,
+				cmd: &CommandInteraction,
+			) -> sqlx::Result<Option<CreateInteractionResponse>> {
+				let res = match cmd.data.name.as_str() {
+					LeaderboardCommand::NAME | GlobalLeaderboardCommand::NAME => {
+						leaderboard_autocomplete(self.db_handler.as_deref(), cmd).await?
+					}
+					AdminCommand::NAME => admin_autocomplete(self.db_handler.as_deref(), cmd).await?,
+					_ => return Ok(None),
+				};
+
+				Ok(Some(CreateInteractionResponse::Autocomplete(res)))
+			}
 		}
 	};
 }
 
 add_commands!(
-	OptInCommand => opt_in,
-	OptOutCommand => opt_out,
-	SilentCommand => silent,
-	VerboseCommand => verbose,
-	CountsCommand => counts,
-	LeaderboardCommand => leaderboard,
-	MuteAllCommand => mute_all,
-	UnmuteAllCommand => unmute_all,
+	store OptInCommand => opt_in,
+	store OptOutCommand => opt_out,
+	store SilentCommand => silent,
+	store VerboseCommand => verbose,
+	store CountsCommand => counts,
+	store LeaderboardCommand => leaderboard,
+	store GlobalLeaderboardCommand => global_leaderboard,
+	store GlobalOptInCommand => global_opt_in,
+	store GlobalOptOutCommand => global_opt_out,
+	store MuteAllCommand => mute_all,
+	store UnmuteAllCommand => unmute_all,
+	database PatternCommand => pattern,
+	database MatchModeCommand => match_mode,
+	database AliasCommand => alias,
+	database ChannelCommand => channel,
+	database ReplyTemplateCommand => reply_template,
+	database LanguageCommand => language,
+	database ExportMyDataCommand => export_my_data,
+	database DeleteMyDataCommand => delete_my_data,
+	database DeleteUserDataCommand => delete_user_data,
+	database AdminCommand => admin,
+	database AuditCommand => audit,
 );
 
+impl Handler {
+	/// Waits until nothing else is counting the message
+	async fn lock_message(&self, message_id: u64) -> MessageLock<'_> {
+		let lock = self
+			.counting
+			.lock()
+			.unwrap()
+			.entry(message_id)
+			.or_default()
+			.clone();
+		MessageLock {
+			counting: &self.counting,
+			message_id,
+			_guard: lock.lock_owned().await,
+		}
+	}
+
+	async fn run_component(
+		&self,
+		component: &ComponentInteraction,
+	) -> sqlx::Result<Option<CreateInteractionResponse>> {
+		let msg = if let Some(msg) = leaderboard_page(&*self.store, component).await? {
+			msg
+		} else if let Some(db) = &self.db_handler {
+			if let Some(msg) = delete_data_confirm(db, component).await? {
+				msg
+			} else if let Some(msg) = reset_server_confirm(db, component).await? {
+				msg
+			} else {
+				return Ok(None);
+			}
+		} else {
+			return Ok(None);
+		};
+
+		Ok(Some(CreateInteractionResponse::UpdateMessage(msg)))
+	}
+
+	/// The channel a thread is in, `None` for other channels
+	async fn thread_parent(ctx: &Context, channel_id: ChannelId) -> Option<ChannelId> {
+		match channel_id.to_channel(ctx).await {
+			Ok(Channel::Guild(channel)) if channel.thread_metadata.is_some() => channel.parent_id,
+			Ok(_) => None,
+			Err(why) => {
+				eprintln!("Could not get channel {channel_id}: {why}");
+				None
+			}
+		}
+	}
+
+	/// Counts the emote the content ends with, returns the reply to send if there should be one.
+	/// `parent_id` finds the channel a thread is in, it's only awaited when there is an emote
+	async fn count(
+		&self,
+		author_id: u64,
+		server_id: u64,
+		channel_id: ChannelId,
+		parent_id: impl Future<Output = Option<ChannelId>> + Send,
+		message: Counting,
+		content: &str,
+	) -> sqlx::Result<Option<String>> {
+		let matcher = self.store.matcher(server_id).await?;
+		let content = content.to_lowercase();
+		let mut found = matcher.find(&content);
+		if !found.is_empty() {
+			let parent_id = parent_id.await;
+			if !self
+				.store
+				.is_channel_counted(server_id, channel_id.get(), parent_id.map(ChannelId::get))
+				.await?
+			{
+				found.clear();
+			}
+		}
+		let channel_id = channel_id.get();
+
+		let counted = match message {
+			Counting::New(_) if found.is_empty() => return Ok(None),
+			Counting::New(message_id) => {
+				self.store
+					.count_message(author_id, server_id, channel_id, message_id, &found)
+					.await?
+			}
+			// an edit without emotes still takes back what the message counted before
+			Counting::Edit(message_id) => {
+				self.store
+					.recount_message(
+						author_id,
+						server_id,
+						channel_id,
+						message_id.get(),
+						message_id.created_at(),
+						&found,
+					)
+					.await?
+			}
+		};
+		let Some(counted) = counted else {
+			return Ok(None);
+		};
+
+		let new_counts = match counted.verbose {
+			VerboseLevel::Verbose => counted.counts,
+			VerboseLevel::Silent => return Ok(None),
+			VerboseLevel::Every(every) => {
+				let every = every.get();
+				// only mention emotes whose count passed a multiple of `every`
+				counted
+					.counts
+					.into_iter()
+					.filter(|c| (c.count - c.added) / every < c.count / every)
+					.collect()
+			}
+		};
+		if new_counts.is_empty() {
+			return Ok(None);
+		}
+
+		let lang = self.store.language(server_id).await?;
+		let templates = self.store.reply_templates(server_id).await?;
+		if templates.is_empty() {
+			return Ok(Some(default_reply(lang, matcher.mode(), &new_counts)));
+		}
+
+		let mut replies = Vec::with_capacity(new_counts.len());
+		for c in &new_counts {
+			let Some(template) = templates
+				.iter()
+				.find(|t| t.emote == c.emote)
+				.or_else(|| templates.iter().find(|t| &*t.emote == ANY_EMOTE))
+			else {
+				replies.push(default_reply(lang, matcher.mode(), slice::from_ref(c)));
+				continue;
+			};
+			let rank = if template.uses_rank() {
+				self.store.user_rank(author_id, server_id, &c.emote).await?
+			} else {
+				None
+			};
+			replies.push(template.render(TemplateValues {
+				user_id: author_id,
+				emote: &c.emote,
+				count: c.count.into(),
+				rank,
+				lang,
+			}));
+		}
+		Ok(Some(replies.join("\n")))
+	}
+}
+
+fn default_reply(lang: Language, mode: MatchMode, counts: &[NewCount]) -> String {
+	let counts_str = counts
+		.iter()
+		.map(|c| {
+			tr!(
+				lang,
+				"*{emote}* **{count}** times",
+				emote = c.emote,
+				count = c.count
+			)
+		})
+		.collect::<Box<[_]>>()
+		.join(", ");
+	match mode {
+		MatchMode::EndOnly => tr!(
+			lang,
+			"You have ended your message with {counts}!",
+			counts = counts_str
+		),
+		MatchMode::Anywhere | MatchMode::AllOccurrences => {
+			tr!(lang, "You have sent {counts}!", counts = counts_str)
+		}
+	}
+}
+
 #[async_trait]
 impl EventHandler for Handler {
 	async fn ready(&self, ctx: Context, ready: Ready) {
@@ -135,6 +397,21 @@ impl EventHandler for Handler {
 		println!("{} is connected!", ready.user.name);
 	}
 
+	async fn guild_delete(
+		&self,
+		_ctx: Context,
+		incomplete: UnavailableGuild,
+		_full: Option<Guild>,
+	) {
+		// an outage doesn't mean the bot left the server
+		if incomplete.unavailable {
+			return;
+		}
+		if let Some(db_handler) = &self.db_handler {
+			db_handler.forget_server(incomplete.id.get());
+		}
+	}
+
 	async fn message(&self, ctx: Context, msg: Message) {
 		if msg.author.bot {
 			return;
@@ -142,85 +419,292 @@ impl EventHandler for Handler {
 		let Some(server_id) = msg.guild_id else {
 			return;
 		};
-		let server_id = server_id.get();
-		let author_id = msg.author.id.get();
-
-		if let Some(found) = self.regex.captures(&msg.content.to_lowercase()) {
-			let Some(emote) = (1..=self.regex_captures).flat_map(|i| found.get(i)).next() else {
-				return;
-			};
-			let emote = emote.as_str();
 
-			let new_count = match self.db_handler.add_one(author_id, server_id, emote).await {
-				Ok(Some(new_count)) => new_count,
-				Ok(None) => return,
-				Err(why) => return eprintln!("DB error: {why}"),
-			};
+		let _lock = self.lock_message(msg.id.get()).await;
+		let reply = match self
+			.count(
+				msg.author.id.get(),
+				server_id.get(),
+				msg.channel_id,
+				Self::thread_parent(&ctx, msg.channel_id),
+				Counting::New(msg.id.get()),
+				&msg.content,
+			)
+			.await
+		{
+			Ok(Some(reply)) => reply,
+			Ok(None) => return,
+			Err(why) => return eprintln!("DB error: {why}"),
+		};
 
-			match self.db_handler.verbose_level(author_id, server_id).await {
-				Ok(VerboseLevel::Verbose) => (),
-				Ok(VerboseLevel::Silent) => return,
-				Ok(VerboseLevel::Every(every)) => {
-					if new_count % every.get() != 0 {
-						return;
-					}
+		match msg.reply(&ctx, reply).await {
+			Ok(reply) => {
+				if let Err(why) = self.store.set_reply(msg.id.get(), reply.id.get()).await {
+					eprintln!("DB error: {why}");
 				}
-				Err(why) => return eprintln!("DB error: {why}"),
-			};
+			}
+			Err(why) => eprintln!("Cound not send message: {why}"),
+		}
+	}
 
-			let try_reply = msg
-				.reply(
+	async fn message_update(
+		&self,
+		ctx: Context,
+		old_if_available: Option<Message>,
+		_new: Option<Message>,
+		event: MessageUpdateEvent,
+	) {
+		let (Some(content), Some(author), Some(server_id)) =
+			(event.content, event.author, event.guild_id)
+		else {
+			return;
+		};
+		// links unfurling into embeds update the message without editing it
+		if author.bot || event.edited_timestamp.is_none() {
+			return;
+		}
+		if old_if_available
+			.as_ref()
+			.is_some_and(|old| old.content == content)
+		{
+			return;
+		}
+		let server_id = server_id.get();
+		let message_id = event.id.get();
+		let _lock = self.lock_message(message_id).await;
+
+		let previous = match self.store.message_events(message_id).await {
+			Ok(previous) => previous,
+			Err(why) => return eprintln!("DB error: {why}"),
+		};
+		let matcher = match self.store.matcher(server_id).await {
+			Ok(matcher) => matcher,
+			Err(why) => return eprintln!("DB error: {why}"),
+		};
+		// messages without events may have been counted before events were recorded,
+		// or had their counts reset, so they are only counted if the edit added the emote
+		if previous.is_empty()
+			&& !old_if_available
+				.is_some_and(|old| matcher.find(&old.content.to_lowercase()).is_empty())
+		{
+			return;
+		}
+		let lowercase = content.to_lowercase();
+		let mut found: Vec<_> = matcher.find(&lowercase).iter().map(|f| f.emote).collect();
+		let mut counted: Vec<_> = previous.iter().map(|event| event.emote.as_ref()).collect();
+		found.sort_unstable();
+		counted.sort_unstable();
+		if found == counted {
+			return;
+		}
+
+		let reply = match self
+			.count(
+				author.id.get(),
+				server_id,
+				event.channel_id,
+				Self::thread_parent(&ctx, event.channel_id),
+				Counting::Edit(event.id),
+				&content,
+			)
+			.await
+		{
+			Ok(reply) => reply,
+			Err(why) => return eprintln!("DB error: {why}"),
+		};
+
+		let old_reply = previous.iter().find_map(|event| event.reply_id);
+		let new_reply = match (reply, old_reply) {
+			(Some(reply), Some(old_reply)) => event
+				.channel_id
+				.edit_message(&ctx, old_reply.0, EditMessage::new().content(reply))
+				.await
+				.map(Some),
+			(Some(reply), None) => event
+				.channel_id
+				.send_message(
 					&ctx,
-					format!("You have ended your message with *{emote}* **{new_count}** times!",),
+					CreateMessage::new()
+						.content(reply)
+						.reference_message((event.channel_id, event.id))
+						.allowed_mentions(CreateAllowedMentions::new()),
 				)
-				.await;
-			if let Err(why) = try_reply {
-				eprintln!("Cound not send message: {why}");
+				.await
+				.map(Some),
+			(None, Some(old_reply)) => event
+				.channel_id
+				.delete_message(&ctx, old_reply.0)
+				.await
+				.map(|_| None),
+			(None, None) => Ok(None),
+		};
+
+		match new_reply {
+			Ok(Some(reply)) => {
+				if let Err(why) = self.store.set_reply(message_id, reply.id.get()).await {
+					eprintln!("DB error: {why}");
+				}
 			}
+			Ok(None) => (),
+			Err(why) => eprintln!("Could not update reply: {why}"),
 		}
 	}
 
-	async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
-		let Interaction::Command(command) = interaction else {
+	async fn message_delete(
+		&self,
+		_ctx: Context,
+		_channel_id: ChannelId,
+		deleted_message_id: MessageId,
+		guild_id: Option<GuildId>,
+	) {
+		if guild_id.is_none() {
+			return;
+		}
+		let _lock = self.lock_message(deleted_message_id.get()).await;
+		if let Err(why) = self
+			.store
+			.remove_messages(&[deleted_message_id.get()])
+			.await
+		{
+			eprintln!("DB error: {why}");
+		}
+	}
+
+	async fn message_delete_bulk(
+		&self,
+		_ctx: Context,
+		_channel_id: ChannelId,
+		multiple_deleted_messages_ids: Vec<MessageId>,
+		guild_id: Option<GuildId>,
+	) {
+		if guild_id.is_none() {
 			return;
+		}
+		let ids: Vec<_> = multiple_deleted_messages_ids
+			.iter()
+			.map(|id| id.get())
+			.collect();
+		let mut locks = Vec::with_capacity(ids.len());
+		for &id in &ids {
+			locks.push(self.lock_message(id).await);
+		}
+		if let Err(why) = self.store.remove_messages(&ids).await {
+			eprintln!("DB error: {why}");
+		}
+	}
+
+	async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+		let response = match &interaction {
+			Interaction::Command(command) => self.run_command(command).await,
+			Interaction::Autocomplete(command) => self.run_autocomplete(command).await,
+			Interaction::Component(component) => self.run_component(component).await,
+			_ => return,
 		};
 
-		let response = match self.run_command(&command).await {
+		let response = match response {
 			Ok(Some(r)) => r,
 			Ok(None) => return,
 			Err(why) => return eprintln!("DB error: {why}"),
 		};
 
-		if let Err(why) = command.create_response(&ctx.http, response).await {
-			eprintln!("Cannot respond to slash command: {why}");
+		let sent = match &interaction {
+			Interaction::Command(command) | Interaction::Autocomplete(command) => {
+				command.create_response(&ctx.http, response).await
+			}
+			Interaction::Component(component) => {
+				component.create_response(&ctx.http, response).await
+			}
+			_ => return,
+		};
+		if let Err(why) = sent {
+			eprintln!("Cannot respond to interaction: {why}");
 		}
 	}
 }
 
+async fn connect() -> DatabaseHandler {
+	let db_url = env::var("DATABASE_URL").expect("Expected a DATABASE_URL in the environment");
+	DatabaseHandler::connect(&db_url).await.unwrap()
+}
+
+/// Prints how often the caches were hit and how much they hold every hour
+async fn log_cache_stats(db_handler: Arc<DatabaseHandler>) {
+	let mut interval = tokio::time::interval(Duration::from_secs(60 * 60));
+	// the first tick finishes right away
+	interval.tick().await;
+	loop {
+		interval.tick().await;
+		let stats = db_handler.cache_stats();
+		println!(
+			"Options cache: {} hits, {} misses, {} cached. Matcher cache: {} hits, {} misses, {} cached. Server settings cache: {} hits, {} misses, {} cached",
+			stats.options_hits,
+			stats.options_misses,
+			stats.cached_options,
+			stats.matcher_hits,
+			stats.matcher_misses,
+			stats.cached_matchers,
+			stats.settings_hits,
+			stats.settings_misses,
+			stats.cached_servers
+		);
+	}
+}
+
 #[tokio::main]
 async fn main() {
 	dotenv().ok();
-	let token = env::var("BOT_TOKEN").expect("Expected a BOT_TOKEN in the environment");
-	let db_url = env::var("DATABASE_URL").expect("Expected a DATABASE_URL in the environment");
 
-	let pool = Pool::connect(&db_url).await.unwrap();
-	let db_handler = DatabaseHandler::new(pool);
+	let handler = match env::args().nth(1).as_deref() {
+		None => {
+			let db_handler = connect().await;
+			if let Err(why) = db_handler.migrate().await {
+				panic!("Could not migrate the database!\n{why}");
+			}
+			let db_handler = Arc::new(db_handler);
+			tokio::spawn(log_cache_stats(db_handler.clone()));
+			Handler {
+				store: db_handler.clone(),
+				db_handler: Some(db_handler),
+				counting: Default::default(),
+			}
+		}
+		Some("migrate") => {
+			match connect().await.migrate().await {
+				Ok(()) => println!("Database is up to date"),
+				Err(why) => {
+					eprintln!("Could not migrate the database: {why}");
+					// so scripts running the migrations can tell they failed
+					process::exit(1);
+				}
+			}
+			return;
+		}
+		Some("memory") => {
+			println!("Keeping counts in memory, commands that need the database won't work");
+			Handler {
+				store: Arc::new(MemoryStore::default()),
+				db_handler: None,
+				counting: Default::default(),
+			}
+		}
+		Some(other) => {
+			eprintln!("Unknown subcommand {other}, expected migrate or memory");
+			process::exit(2);
+		}
+	};
 
-	let regex = include_str!("regex.txt").trim();
-	let regex_captures = regex.chars().filter(|c| *c == '(').count();
-	let regex = Regex::new(regex).expect("Expected a valid regex expression");
+	let token = env::var("BOT_TOKEN").expect("Expected a BOT_TOKEN in the environment");
 
-	let intents = GatewayIntents::GUILD_MESSAGES
+	let intents = GatewayIntents::GUILDS
+		| GatewayIntents::GUILD_MESSAGES
 		| GatewayIntents::DIRECT_MESSAGES
 		| GatewayIntents::MESSAGE_CONTENT;
 
-	let handler = Handler {
-		regex,
-		regex_captures,
-		db_handler,
-	};
+	let mut cache_settings = CacheSettings::default();
+	cache_settings.max_messages = MAX_CACHED_MESSAGES;
 
 	let mut client = Client::builder(&token, intents)
+		.cache_settings(cache_settings)
 		.event_handler(handler)
 		.await
 		.expect("Err creating client");
@@ -229,3 +713,107 @@ async fn main() {
 		eprintln!("Client error: {why:?}");
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use std::future;
+
+	use super::*;
+
+	const SERVER: u64 = 10;
+	const USER: u64 = 1;
+
+	fn handler() -> Handler {
+		Handler {
+			store: Arc::new(MemoryStore::default()),
+			db_handler: None,
+			counting: Default::default(),
+		}
+	}
+
+	async fn count(handler: &Handler, message: Counting, content: &str) -> Option<String> {
+		handler
+			.count(
+				USER,
+				SERVER,
+				ChannelId::new(2),
+				future::ready(None),
+				message,
+				content,
+			)
+			.await
+			.unwrap()
+	}
+
+	#[tokio::test]
+	async fn counts_the_emote_a_message_ends_with() {
+		let handler = handler();
+		assert_eq!(
+			count(&handler, Counting::New(100), "hello :3")
+				.await
+				.as_deref(),
+			Some("You have ended your message with *:3* **1** times!")
+		);
+		assert_eq!(count(&handler, Counting::New(101), "no emote").await, None);
+		assert_eq!(count(&handler, Counting::New(102), ":3 first").await, None);
+		assert_eq!(
+			count(&handler, Counting::New(103), "again :3")
+				.await
+				.as_deref(),
+			Some("You have ended your message with *:3* **2** times!")
+		);
+	}
+
+	#[tokio::test]
+	async fn replies_follow_the_verbosity() {
+		let handler = handler();
+		handler
+			.store
+			.set_silent(USER, Some(SERVER), Some(2))
+			.await
+			.unwrap();
+		// only counts passing a multiple of 2 are mentioned
+		assert_eq!(count(&handler, Counting::New(100), ":3").await, None);
+		assert_eq!(
+			count(&handler, Counting::New(101), ":3").await.as_deref(),
+			Some("You have ended your message with *:3* **2** times!")
+		);
+
+		handler.store.mute_all(SERVER, Some(0)).await.unwrap();
+		assert_eq!(count(&handler, Counting::New(102), ":3").await, None);
+		let counts = handler.store.user_counts(USER, Some(SERVER)).await.unwrap();
+		assert_eq!(counts[0].count.0, 3);
+	}
+
+	#[tokio::test]
+	async fn edits_replace_what_the_message_counted() {
+		let handler = handler();
+		let message_id = MessageId::new(100);
+		count(&handler, Counting::New(message_id.get()), "hi :3").await;
+
+		assert_eq!(
+			count(&handler, Counting::Edit(message_id), "hi").await,
+			None
+		);
+		assert!(handler
+			.store
+			.user_counts(USER, Some(SERVER))
+			.await
+			.unwrap()
+			.is_empty());
+
+		assert_eq!(
+			count(&handler, Counting::Edit(message_id), "hi owo")
+				.await
+				.as_deref(),
+			Some("You have ended your message with *owo* **1** times!")
+		);
+		let events = handler
+			.store
+			.message_events(message_id.get())
+			.await
+			.unwrap();
+		let emotes: Vec<_> = events.iter().map(|event| &*event.emote).collect();
+		assert_eq!(emotes, ["owo"]);
+	}
+}
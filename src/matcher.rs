@@ -0,0 +1,394 @@
+use std::{borrow::Cow, collections::HashMap, sync::LazyLock};
+
+use regex::{Captures, Regex};
+
+const DEFAULT_PATTERN: &str = include_str!("regex.txt");
+static CUSTOM_EMOJI: LazyLock<Regex> = LazyLock::new(|| {
+	Regex::new(r"^<a?:([a-zA-Z0-9_]{2,32}):(\d+)>$").expect("Expected a valid regex expression")
+});
+static UNICODE_EMOJI: LazyLock<Regex> = LazyLock::new(|| {
+	Regex::new(r"^[\p{Emoji}\p{Emoji_Component}\u{200D}\u{FE0F}]+$")
+		.expect("Expected a valid regex expression")
+});
+const VARIATION_SELECTOR: char = '\u{FE0F}';
+
+pub const MAX_PATTERN_LENGTH: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternKind {
+	/// A regex the message has to match
+	Regex,
+	/// A custom Discord emoji, the pattern is its id
+	Custom,
+	/// A unicode emoji sequence
+	Unicode,
+}
+impl PatternKind {
+	pub fn as_str(self) -> &'static str {
+		match self {
+			Self::Regex => "regex",
+			Self::Custom => "custom",
+			Self::Unicode => "unicode",
+		}
+	}
+}
+impl From<String> for PatternKind {
+	fn from(value: String) -> Self {
+		match value.as_str() {
+			"custom" => Self::Custom,
+			"unicode" => Self::Unicode,
+			_ => Self::Regex,
+		}
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct ServerPattern {
+	pub emote: Box<str>,
+	pub pattern: Box<str>,
+	pub kind: PatternKind,
+}
+
+impl ServerPattern {
+	/// Parses a custom emoji (`<:name:id>`) or a unicode emoji into a pattern counting it
+	pub fn from_emoji(emoji: &str) -> Option<Self> {
+		let emoji = emoji.trim();
+		if let Some(custom) = CUSTOM_EMOJI.captures(emoji) {
+			return Some(Self {
+				emote: emoji.into(),
+				pattern: custom.get(2)?.as_str().into(),
+				kind: PatternKind::Custom,
+			});
+		}
+
+		let is_emoji = !emoji.is_ascii() && UNICODE_EMOJI.is_match(emoji);
+		is_emoji.then(|| Self {
+			emote: emoji.into(),
+			pattern: emoji.replace(VARIATION_SELECTOR, "").into(),
+			kind: PatternKind::Unicode,
+		})
+	}
+
+	fn regex(&self) -> Cow<'_, str> {
+		match self.kind {
+			PatternKind::Regex => Cow::Borrowed(&self.pattern),
+			// names are matched loosely since renaming an emoji keeps its id
+			PatternKind::Custom => Cow::Owned(format!(r"<a?:[a-z0-9_]+:{}>", self.pattern)),
+			// clients don't always send the variation selector
+			PatternKind::Unicode => Cow::Owned(
+				self.pattern
+					.chars()
+					.map(|c| format!(r"{}\u{{FE0F}}?", regex::escape(c.encode_utf8(&mut [0; 4]))))
+					.collect(),
+			),
+		}
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct EmoteAlias {
+	pub alias: Box<str>,
+	pub emote: Box<str>,
+}
+
+/// The text to show for an emote where custom emojis can't render
+pub fn emote_label(emote: &str) -> Cow<'_, str> {
+	match CUSTOM_EMOJI
+		.captures(emote)
+		.and_then(|custom| custom.get(1))
+	{
+		Some(name) => Cow::Owned(format!(":{}:", name.as_str())),
+		None => Cow::Borrowed(emote),
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatchMode {
+	/// Count the emote the message ends with
+	#[default]
+	EndOnly,
+	/// Count the first emote anywhere in the message
+	Anywhere,
+	/// Count every emote in the message
+	AllOccurrences,
+}
+impl MatchMode {
+	pub fn from_choice(choice: &str) -> Option<Self> {
+		match choice {
+			"end_only" => Some(Self::EndOnly),
+			"anywhere" => Some(Self::Anywhere),
+			"all_occurrences" => Some(Self::AllOccurrences),
+			_ => None,
+		}
+	}
+
+	pub fn as_str(self) -> &'static str {
+		match self {
+			Self::EndOnly => "end_only",
+			Self::Anywhere => "anywhere",
+			Self::AllOccurrences => "all_occurrences",
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Found<'a> {
+	/// The canonical emote, after aliases are applied
+	pub emote: &'a str,
+	/// The emote as it was matched
+	pub variant: &'a str,
+	/// The whole text that matched
+	pub text: &'a str,
+}
+
+#[derive(Debug)]
+enum Group {
+	/// The captured text is the emote itself
+	Text,
+	/// The group matches the given canonical emote
+	Emote(Box<str>),
+	/// A group nested inside a server pattern
+	Inner,
+}
+
+#[derive(Debug)]
+pub struct Matcher {
+	regex: Regex,
+	groups: Vec<Group>,
+	aliases: HashMap<Box<str>, Box<str>>,
+	mode: MatchMode,
+}
+
+impl Default for Matcher {
+	fn default() -> Self {
+		Self::new(&[], &[], MatchMode::default()).expect("Expected a valid regex expression")
+	}
+}
+
+impl Matcher {
+	pub fn new(
+		patterns: &[ServerPattern],
+		aliases: &[EmoteAlias],
+		mode: MatchMode,
+	) -> Result<Self, regex::Error> {
+		let mut groups = Vec::new();
+		let mut alternatives = Vec::new();
+
+		for pattern in patterns {
+			let regex = pattern.regex();
+			let inner = Regex::new(&regex)?.captures_len() - 1;
+			groups.push(Group::Emote(pattern.emote.clone()));
+			groups.extend((0..inner).map(|_| Group::Inner));
+			alternatives.push(format!("((?i:{regex}))"));
+		}
+
+		let default = DEFAULT_PATTERN.trim();
+		groups.extend((1..Regex::new(default)?.captures_len()).map(|_| Group::Text));
+		alternatives.push(default.to_owned());
+
+		let anchor = match mode {
+			MatchMode::EndOnly => "$",
+			MatchMode::Anywhere | MatchMode::AllOccurrences => "",
+		};
+		let regex = Regex::new(&format!("(?:{}){anchor}", alternatives.join("|")))?;
+		let aliases = aliases
+			.iter()
+			.map(|alias| (alias.alias.clone(), alias.emote.clone()))
+			.collect();
+		Ok(Self {
+			regex,
+			groups,
+			aliases,
+			mode,
+		})
+	}
+
+	pub fn mode(&self) -> MatchMode {
+		self.mode
+	}
+
+	/// Checks that a server pattern compiles and can't match an empty message
+	pub fn validate(pattern: &str) -> Result<(), regex::Error> {
+		let regex = Regex::new(&format!("(?i:{pattern})"))?;
+		if regex.is_match("") {
+			return Err(regex::Error::Syntax(
+				"pattern must not match an empty message".to_owned(),
+			));
+		}
+		Ok(())
+	}
+
+	/// Whether messages can be counted under the canonical emote
+	pub fn counts(&self, emote: &str) -> bool {
+		let pattern = self
+			.groups
+			.iter()
+			.any(|group| matches!(group, Group::Emote(e) if **e == *emote));
+		let alias = self.aliases.values().any(|e| **e == *emote);
+		// a default emote finds itself when it's the whole text
+		let default = self
+			.regex
+			.captures(emote)
+			.filter(|captures| captures.get(0).is_some_and(|m| m.len() == emote.len()))
+			.and_then(|captures| self.found(captures))
+			.is_some_and(|found| found.emote == emote);
+		pattern || alias || default
+	}
+
+	/// Returns the emotes to count in the (lowercase) content, depending on the match mode
+	pub fn find<'a>(&'a self, content: &'a str) -> Vec<Found<'a>> {
+		let mut found = self
+			.regex
+			.captures_iter(content)
+			.filter(|captures| {
+				// emotes in the middle of a message have to end on a word boundary,
+				// so "meowing" is not a meow
+				let end = captures.get(0).map_or(0, |m| m.end());
+				!content[end..]
+					.chars()
+					.next()
+					.is_some_and(char::is_alphanumeric)
+			})
+			.filter_map(|captures| self.found(captures));
+		match self.mode {
+			MatchMode::EndOnly | MatchMode::Anywhere => found.next().into_iter().collect(),
+			MatchMode::AllOccurrences => found.collect(),
+		}
+	}
+
+	fn found<'a>(&'a self, captures: Captures<'a>) -> Option<Found<'a>> {
+		let variant =
+			self.groups
+				.iter()
+				.zip(captures.iter().skip(1))
+				.find_map(|(group, capture)| match (group, capture) {
+					(Group::Text, Some(capture)) => Some(capture.as_str()),
+					(Group::Emote(emote), Some(_)) => Some(emote.as_ref()),
+					_ => None,
+				})?;
+		Some(Found {
+			emote: self.aliases.get(variant).map_or(variant, AsRef::as_ref),
+			variant,
+			text: captures.get(0)?.as_str(),
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// The canonical emote and variant of everything found
+	fn find<'a>(matcher: &'a Matcher, content: &'a str) -> Vec<(&'a str, &'a str)> {
+		matcher
+			.find(content)
+			.into_iter()
+			.map(|found| (found.emote, found.variant))
+			.collect()
+	}
+
+	fn matcher(mode: MatchMode) -> Matcher {
+		Matcher::new(&[], &[], mode).unwrap()
+	}
+
+	#[test]
+	fn end_only_counts_the_last_emote() {
+		let matcher = matcher(MatchMode::EndOnly);
+		assert_eq!(find(&matcher, "hi x3"), [("x3", "x3")]);
+		assert_eq!(find(&matcher, "owo hi :33c"), [(":3", ":3")]);
+		assert_eq!(find(&matcher, "meowww"), [("meow", "meow")]);
+		assert!(find(&matcher, "x3 hi").is_empty());
+	}
+
+	#[test]
+	fn anywhere_counts_the_first_emote() {
+		let matcher = matcher(MatchMode::Anywhere);
+		assert_eq!(find(&matcher, "uwu hi ^w^"), [("uwu", "uwu")]);
+		// emotes in the middle have to end on a word boundary
+		assert_eq!(find(&matcher, "meowing x3"), [("x3", "x3")]);
+		assert!(find(&matcher, "meowing").is_empty());
+	}
+
+	#[test]
+	fn all_occurrences_counts_every_emote() {
+		let matcher = matcher(MatchMode::AllOccurrences);
+		assert_eq!(
+			find(&matcher, ":3 and x3 owo"),
+			[(":3", ":3"), ("x3", "x3"), ("owo", "owo")]
+		);
+	}
+
+	#[test]
+	fn server_patterns_count_as_their_emote() {
+		let custom = ServerPattern::from_emoji("<:Blob:123>").unwrap();
+		let unicode = ServerPattern::from_emoji("❤️").unwrap();
+		let regex = ServerPattern {
+			emote: "nya".into(),
+			pattern: "nya+".into(),
+			kind: PatternKind::Regex,
+		};
+		let matcher =
+			Matcher::new(&[custom, unicode, regex], &[], MatchMode::AllOccurrences).unwrap();
+		assert_eq!(
+			find(&matcher, "nyaaa <:blob_renamed:123> ❤ x3"),
+			[
+				("nya", "nya"),
+				("<:Blob:123>", "<:Blob:123>"),
+				("❤️", "❤️"),
+				("x3", "x3")
+			]
+		);
+	}
+
+	#[test]
+	fn aliases_count_as_their_emote() {
+		let aliases = [EmoteAlias {
+			alias: "owo".into(),
+			emote: "uwu".into(),
+		}];
+		let matcher = Matcher::new(&[], &aliases, MatchMode::EndOnly).unwrap();
+		assert_eq!(find(&matcher, "hi owo"), [("uwu", "owo")]);
+		assert_eq!(find(&matcher, "hi uwu"), [("uwu", "uwu")]);
+	}
+
+	#[test]
+	fn counts_only_canonical_emotes() {
+		let pattern = ServerPattern {
+			emote: "nya".into(),
+			pattern: "nya+".into(),
+			kind: PatternKind::Regex,
+		};
+		let aliases = [EmoteAlias {
+			alias: "nyan".into(),
+			emote: "nya".into(),
+		}];
+		let matcher = Matcher::new(&[pattern], &aliases, MatchMode::EndOnly).unwrap();
+		for emote in [":3", ";3", "x3", "meow", "^w^", "^^", "jorp!", "nya"] {
+			assert!(matcher.counts(emote), "{emote}");
+		}
+		for emote in ["*", ":33", "meoww", "nyaa", "blob"] {
+			assert!(!matcher.counts(emote), "{emote}");
+		}
+	}
+
+	#[test]
+	fn validates_patterns() {
+		assert!(Matcher::validate("nya+").is_ok());
+		assert!(Matcher::validate("(nya").is_err());
+		assert!(Matcher::validate("a*").is_err());
+	}
+
+	#[test]
+	fn parses_emojis() {
+		let custom = ServerPattern::from_emoji(" <a:Blob:123> ").unwrap();
+		assert_eq!(&*custom.pattern, "123");
+		assert_eq!(custom.kind, PatternKind::Custom);
+		assert_eq!(emote_label(&custom.emote), ":Blob:");
+		assert_eq!(emote_label("x3"), "x3");
+
+		let unicode = ServerPattern::from_emoji("❤️").unwrap();
+		assert_eq!(&*unicode.pattern, "❤");
+		assert_eq!(unicode.kind, PatternKind::Unicode);
+		assert!(ServerPattern::from_emoji("x3").is_none());
+	}
+}
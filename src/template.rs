@@ -0,0 +1,202 @@
+use crate::{locale::Language, tr};
+
+pub const MAX_TEMPLATE_LENGTH: usize = 300;
+/// The emote of the template used for emotes without their own
+pub const ANY_EMOTE: &str = "*";
+
+#[derive(Debug, Clone)]
+pub struct ReplyTemplate {
+	pub emote: Box<str>,
+	pub template: Box<str>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Placeholder {
+	User,
+	Emote,
+	Count,
+	Ordinal,
+	Rank,
+}
+impl Placeholder {
+	fn from_name(name: &str) -> Option<Self> {
+		match name {
+			"user" => Some(Self::User),
+			"emote" => Some(Self::Emote),
+			"count" => Some(Self::Count),
+			"ordinal" => Some(Self::Ordinal),
+			"rank" => Some(Self::Rank),
+			_ => None,
+		}
+	}
+}
+
+enum Part<'a> {
+	Text(&'a str),
+	Placeholder(Placeholder),
+}
+
+#[derive(Debug)]
+pub enum TemplateError {
+	Empty,
+	TooLong,
+	Unclosed,
+	Unknown(Box<str>),
+}
+impl TemplateError {
+	pub fn message(&self, lang: Language) -> String {
+		match self {
+			Self::Empty => tr!(lang, "the template is empty"),
+			Self::TooLong => tr!(
+				lang,
+				"the template is longer than {max} characters",
+				max = MAX_TEMPLATE_LENGTH
+			),
+			Self::Unclosed => tr!(lang, "a `{` is never closed"),
+			Self::Unknown(name) => tr!(
+				lang,
+				"`{{name}}` is not a placeholder, use {user}, {emote}, {count}, {ordinal} or {rank}",
+				name = name
+			),
+		}
+	}
+}
+
+/// The values to fill in a template with
+#[derive(Debug, Clone, Copy)]
+pub struct TemplateValues<'a> {
+	pub user_id: u64,
+	pub emote: &'a str,
+	pub count: i64,
+	pub rank: Option<i64>,
+	pub lang: Language,
+}
+
+impl ReplyTemplate {
+	/// Checks that the template is short enough and only uses known placeholders
+	pub fn validate(template: &str) -> Result<(), TemplateError> {
+		if template.trim().is_empty() {
+			return Err(TemplateError::Empty);
+		}
+		if template.chars().count() > MAX_TEMPLATE_LENGTH {
+			return Err(TemplateError::TooLong);
+		}
+		parts(template).map(|_| ())
+	}
+
+	/// Whether filling in the template needs the user's rank
+	pub fn uses_rank(&self) -> bool {
+		parts(&self.template).is_ok_and(|parts| {
+			parts
+				.iter()
+				.any(|part| matches!(part, Part::Placeholder(Placeholder::Rank)))
+		})
+	}
+
+	pub fn render(&self, values: TemplateValues) -> String {
+		render(&self.template, values)
+	}
+}
+
+/// Fills in a template, invalid placeholders are kept as they are
+pub fn render(template: &str, values: TemplateValues) -> String {
+	let Ok(parts) = parts(template) else {
+		return template.to_owned();
+	};
+	parts
+		.into_iter()
+		.map(|part| match part {
+			Part::Text(text) => text.to_owned(),
+			Part::Placeholder(Placeholder::User) => format!("<@{}>", values.user_id),
+			Part::Placeholder(Placeholder::Emote) => values.emote.to_owned(),
+			Part::Placeholder(Placeholder::Count) => values.count.to_string(),
+			Part::Placeholder(Placeholder::Ordinal) => values.lang.ordinal(values.count),
+			Part::Placeholder(Placeholder::Rank) => match values.rank {
+				Some(rank) => format!("#{rank}"),
+				None => values.lang.text("unranked").to_owned(),
+			},
+		})
+		.collect()
+}
+
+fn parts(template: &str) -> Result<Vec<Part<'_>>, TemplateError> {
+	let mut parts = Vec::new();
+	let mut rest = template;
+	while let Some(start) = rest.find('{') {
+		if start > 0 {
+			parts.push(Part::Text(&rest[..start]));
+		}
+		let Some(len) = rest[start..].find('}') else {
+			return Err(TemplateError::Unclosed);
+		};
+		let name = &rest[start + 1..start + len];
+		let placeholder = Placeholder::from_name(name.trim())
+			.ok_or_else(|| TemplateError::Unknown(name.into()))?;
+		parts.push(Part::Placeholder(placeholder));
+		rest = &rest[start + len + 1..];
+	}
+	if !rest.is_empty() {
+		parts.push(Part::Text(rest));
+	}
+	Ok(parts)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn values(rank: Option<i64>) -> TemplateValues<'static> {
+		TemplateValues {
+			user_id: 1,
+			emote: "x3",
+			count: 22,
+			rank,
+			lang: Language::English,
+		}
+	}
+
+	#[test]
+	fn renders_placeholders() {
+		assert_eq!(
+			render(
+				"{user} has {count} {emote}s, the {ordinal} is {rank}",
+				values(Some(3))
+			),
+			"<@1> has 22 x3s, the 22nd is #3"
+		);
+		assert_eq!(render("{ rank }", values(None)), "unranked");
+		// invalid templates are kept as they are
+		assert_eq!(render("{nope}", values(None)), "{nope}");
+	}
+
+	#[test]
+	fn validates_templates() {
+		assert!(ReplyTemplate::validate("{user} {count}").is_ok());
+		assert!(matches!(
+			ReplyTemplate::validate("  "),
+			Err(TemplateError::Empty)
+		));
+		assert!(matches!(
+			ReplyTemplate::validate(&"a".repeat(MAX_TEMPLATE_LENGTH + 1)),
+			Err(TemplateError::TooLong)
+		));
+		assert!(matches!(
+			ReplyTemplate::validate("{user"),
+			Err(TemplateError::Unclosed)
+		));
+		assert!(matches!(
+			ReplyTemplate::validate("{nope}"),
+			Err(TemplateError::Unknown(name)) if &*name == "nope"
+		));
+	}
+
+	#[test]
+	fn knows_when_the_rank_is_used() {
+		let template = |template: &str| ReplyTemplate {
+			emote: ANY_EMOTE.into(),
+			template: template.into(),
+		};
+		assert!(template("you're {rank}").uses_rank());
+		assert!(!template("{count}").uses_rank());
+	}
+}